@@ -1,11 +1,17 @@
-use crate::{opt::Command, util::deserialize_config, Options};
-use lib_pendulum_launch::{sub_command, util, Error, Launcher, Result};
+use crate::{
+    opt::Command,
+    util::{deserialize_config, locate_config, search_default_config},
+    Options,
+};
+use lib_pendulum_launch::{
+    sub_command, util, Config, Error, Launcher, LogFilter, NodeOutput, PathBuffer, Result,
+    SpecPatch,
+};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
 pub struct App {
     options: Options,
-    launcher: Launcher,
 }
 
 impl App {
@@ -20,41 +26,75 @@ impl App {
                     collator_bin,
                     collator_spec,
                     name,
+                    para_id,
                     outdir,
                 } => self.export_genesis(
                     collator_bin.to_owned(),
                     collator_spec.to_owned(),
                     name.to_owned(),
+                    para_id.to_owned(),
                     outdir.to_owned(),
                 )?,
                 Command::GenerateSpecs {
                     collator_bin,
+                    chain,
                     name,
                     para_id,
                     outdir,
+                    patches,
                 } => self.generate_specs(
                     collator_bin.to_owned(),
+                    chain.to_owned(),
                     name.to_owned(),
                     para_id.to_owned(),
                     outdir.to_owned(),
+                    patches,
+                )?,
+                Command::GenerateRelaySpecs {
+                    validator_bin,
+                    chain,
+                    validator_keys,
+                    name,
+                    outdir,
+                } => self.generate_relay_specs(
+                    validator_bin.to_owned(),
+                    chain.to_owned(),
+                    validator_keys.to_owned(),
+                    name.to_owned(),
+                    outdir.to_owned(),
                 )?,
+                Command::Validate => {
+                    self.launcher()?;
+                    println!("Config is valid");
+                }
+                Command::ConvertConfig { output } => self.convert_config(output.to_owned())?,
                 Command::GenerateDocker {
                     outdir,
                     enable_volume,
                 } => self.generate_docker(outdir.to_owned(), enable_volume.to_owned())?,
             },
-            None => self.launcher.run()?,
+            None => self.launch()?,
         };
 
         Ok(())
     }
 
-    /// Export genesis data to an `outdir` if provided or to the project root
+    fn launch(&self) -> Result<()> {
+        let mut launcher = self.launcher()?;
+        if let Some(path) = &self.options.port_map {
+            launcher.port_map().write(path)?;
+        }
+
+        launcher.run()
+    }
+
+    /// Export genesis data and its manifest to an `outdir` if provided or to the project root
     fn export_genesis(
         &self,
         bin: PathBuf,
         chain: PathBuf,
         name: Option<String>,
+        para_id: Option<u32>,
         outdir: Option<PathBuf>,
     ) -> Result<()> {
         let bin = util::path_to_string(&bin)?;
@@ -62,48 +102,124 @@ impl App {
         let name = name.unwrap_or_else(|| "local-chain".to_string());
         let outdir = util::path_to_string(&outdir.unwrap_or(util::locate_project_root()?))?;
 
-        sub_command::export_genesis(bin, chain, name, outdir)
+        sub_command::export_genesis(bin, chain, name, outdir, para_id)
     }
 
-    /// Generate specs from a collator
+    /// Generate specs from a collator, applying the genesis and patches of the parachain with
+    /// `para_id` if the config has one, followed by `patches`
     fn generate_specs(
         &self,
         bin: PathBuf,
+        chain: Option<String>,
         name: Option<String>,
         para_id: Option<u32>,
         outdir: Option<PathBuf>,
+        patches: &[PathBuf],
     ) -> Result<()> {
         let bin = util::path_to_string(&bin)?;
         let name = name.unwrap_or_else(|| "local-chain".to_string());
         let para_id = para_id.unwrap_or(2000);
         let outdir = util::path_to_string(&outdir.unwrap_or(util::locate_project_root()?))?;
 
-        sub_command::generate_specs(bin, name, para_id, outdir)
+        // Without an explicit config, specs can be generated outside of any project
+        let config = match &self.options.config {
+            Some(_) => Some(deserialize_config(&self.options.config)?),
+            None => match search_default_config().ok().flatten() {
+                Some(path) => Some(Config::deserialize(path)?),
+                None => None,
+            },
+        };
+        let parachain = config.as_ref().and_then(|config| config.parachain(para_id));
+        let patches: Vec<_> = patches
+            .iter()
+            .map(|path| SpecPatch::File(PathBuffer::from(path.to_owned())))
+            .collect();
+
+        sub_command::generate_specs(bin, chain, name, para_id, outdir, parachain, &patches)
+    }
+
+    /// Generate relay chain specs from a validator, registering every parachain of the config
+    /// with the genesis exported by its collator and, with `validator_keys`, making its
+    /// validators the authorities
+    fn generate_relay_specs(
+        &self,
+        bin: Option<PathBuf>,
+        chain: String,
+        validator_keys: bool,
+        name: Option<String>,
+        outdir: Option<PathBuf>,
+    ) -> Result<()> {
+        let config = deserialize_config(&self.options.config)?;
+        let bin = match bin {
+            Some(bin) => util::path_to_string(&bin)?,
+            None => config.validator.bin().to_string()?,
+        };
+        let name = name.unwrap_or_else(|| "local-relay".to_string());
+        let outdir = util::path_to_string(&outdir.unwrap_or(util::locate_project_root()?))?;
+
+        let validators = match validator_keys {
+            true => config.validator_seeds(),
+            false => Vec::new(),
+        };
+
+        sub_command::generate_relay_specs(bin, chain, name, outdir, &config.parachains, &validators)
     }
 
     fn generate_docker(&self, out_dir: Option<PathBuf>, enable_volume: bool) -> Result<()> {
         let out_dir = util::path_to_string(&out_dir.unwrap_or(util::locate_project_root()?))?;
-        let command = sub_command::GenerateDocker::new(&self.launcher, out_dir, enable_volume);
+        let launcher = self.launcher()?;
+        let command = sub_command::GenerateDocker::new(&launcher, out_dir, enable_volume);
 
         command.execute()
     }
+
+    /// Convert the config to the format given by the extension of `output`
+    fn convert_config(&self, output: PathBuf) -> Result<()> {
+        let config = Config::parse(locate_config(&self.options.config)?)?;
+
+        config.serialize(output)
+    }
+
+    /// Deserializes and validates the config, creating a launcher from it
+    fn launcher(&self) -> Result<Launcher> {
+        let config = deserialize_config(&self.options.config)?;
+        config.validate()?;
+        for warning in config.warnings() {
+            eprintln!("Warning: {}", warning);
+        }
+
+        let output = NodeOutput {
+            log_dir: self.options.log.to_owned().map(PathBuffer::from),
+            stderr: self.options.stderr,
+            keep_runs: self.options.keep_runs,
+            max_log_size: self.options.max_log_size.saturating_mul(1024 * 1024),
+            filter: LogFilter {
+                nodes: self.options.nodes.to_owned(),
+                level: self.options.log_level,
+            },
+            quiet: self.options.quiet,
+        };
+        let mut launcher = Launcher::new(config, output)?;
+        launcher.ensure_unique_ports()?;
+
+        let workspace = &mut launcher.context.workspace;
+        workspace.purge = self.options.purge;
+        workspace.cleanup |= self.options.cleanup;
+
+        Ok(launcher)
+    }
 }
 
 impl TryFrom<Options> for App {
     type Error = Error;
 
     fn try_from(options: Options) -> Result<Self> {
-        let (quiet, log) = (options.quiet, options.log.to_owned());
-        if quiet && log.is_some() {
+        if options.quiet && options.log.is_some() {
             return Err(Error::ProcessFailed(
                 "Cannot use `--quiet` and `--log <DIR>` together".to_string(),
             ));
         }
 
-        let config = deserialize_config(&options.config)?;
-        let launcher = Launcher::new(config, log)?;
-        launcher.ensure_unique_ports()?;
-
-        Ok(Self { options, launcher })
+        Ok(Self { options })
     }
 }
@@ -3,9 +3,12 @@ mod opt;
 mod util;
 
 use app::App;
-use lib_pendulum_launch::Result;
 use opt::Options;
+use std::process;
 
-fn main() -> Result<()> {
-    App::from_args()?.run()
+fn main() {
+    if let Err(err) = App::from_args().and_then(|mut app| app.run()) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
 }
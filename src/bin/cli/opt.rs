@@ -1,3 +1,4 @@
+use lib_pendulum_launch::{LogLevel, StderrLog};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -7,10 +8,16 @@ pub enum Command {
     ExportGenesis {
         #[structopt(name = "bin", parse(from_os_str), about = "Collator binary")]
         collator_bin: PathBuf,
-        #[structopt(name = "chain", parse(from_os_str), about = "Collator spec")]
+        #[structopt(long = "chain", parse(from_os_str), about = "Collator spec")]
         collator_spec: PathBuf,
         #[structopt(short, long, about = "File prefix")]
         name: Option<String>,
+        #[structopt(
+            short = "i",
+            long,
+            about = "Para id written to the manifest, that of the spec by default"
+        )]
+        para_id: Option<u32>,
         #[structopt(short, long, parse(from_os_str), about = "Alternate output directory")]
         outdir: Option<PathBuf>,
     },
@@ -18,12 +25,55 @@ pub enum Command {
     GenerateSpecs {
         #[structopt(name = "bin", parse(from_os_str), about = "Collator binary")]
         collator_bin: PathBuf,
+        #[structopt(
+            long,
+            about = "Base chain spec, the default chain of the collator otherwise"
+        )]
+        chain: Option<String>,
         #[structopt(short, long, about = "File prefix")]
         name: Option<String>,
         #[structopt(short = "i", long, about = "Para id")]
         para_id: Option<u32>,
         #[structopt(short, long, parse(from_os_str), about = "Alternate output directory")]
         outdir: Option<PathBuf>,
+        #[structopt(
+            short,
+            long = "patch",
+            parse(from_os_str),
+            about = "JSON Patch or merge patch applied to the plain spec, may be repeated"
+        )]
+        patches: Vec<PathBuf>,
+    },
+    #[structopt(about = "Generate relay chain specs registering the parachains of the config")]
+    GenerateRelaySpecs {
+        #[structopt(
+            name = "bin",
+            parse(from_os_str),
+            about = "Validator binary, the one of the config by default"
+        )]
+        validator_bin: Option<PathBuf>,
+        #[structopt(long, default_value = "rococo-local", about = "Base relay chain spec")]
+        chain: String,
+        #[structopt(
+            long,
+            about = "Make the validators of the config the authorities, writing their session keys"
+        )]
+        validator_keys: bool,
+        #[structopt(short, long, about = "File prefix")]
+        name: Option<String>,
+        #[structopt(short, long, parse(from_os_str), about = "Alternate output directory")]
+        outdir: Option<PathBuf>,
+    },
+    #[structopt(about = "Validate the config")]
+    Validate,
+    #[structopt(about = "Convert the config to JSON, TOML or YAML")]
+    ConvertConfig {
+        #[structopt(
+            name = "output",
+            parse(from_os_str),
+            about = "Output path, its extension selects the format"
+        )]
+        output: PathBuf,
     },
     #[structopt(about = "Generate docker-compose.yml")]
     GenerateDocker {
@@ -43,6 +93,43 @@ pub struct Options {
     pub quiet: bool,
     #[structopt(short, long, parse(from_os_str), about = "Directoy to log node data")]
     pub log: Option<PathBuf>,
+    #[structopt(
+        long,
+        default_value = "merged",
+        possible_values = &["merged", "separate"],
+        about = "Log node stderr with stdout or to <name>.err.log"
+    )]
+    pub stderr: StderrLog,
+    #[structopt(
+        long,
+        default_value = "10",
+        about = "Number of runs kept in the log directory"
+    )]
+    pub keep_runs: usize,
+    #[structopt(
+        long,
+        default_value = "100",
+        about = "Size in MiB at which node logs are rotated"
+    )]
+    pub max_log_size: u64,
+    #[structopt(
+        long = "node",
+        name = "node",
+        about = "Only show the output of this node, may be repeated"
+    )]
+    pub nodes: Vec<String>,
+    #[structopt(
+        long,
+        possible_values = &["error", "warn", "info", "debug", "trace"],
+        about = "Least severe level of node output shown"
+    )]
+    pub log_level: Option<LogLevel>,
+    #[structopt(long, parse(from_os_str), about = "Write the allocated ports as JSON")]
+    pub port_map: Option<PathBuf>,
+    #[structopt(long, about = "Delete the chain data of every node before launching")]
+    pub purge: bool,
+    #[structopt(long, about = "Delete the chain data of every node on shutdown")]
+    pub cleanup: bool,
     #[structopt(subcommand)]
     pub cmd: Option<Command>,
 }
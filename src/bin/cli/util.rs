@@ -1,41 +1,30 @@
 use lib_pendulum_launch::{util, Config, Error, Result};
-use std::{
-    fs::{self, DirEntry},
-    io,
-    path::PathBuf,
-};
+use std::path::PathBuf;
+
+/// Config file names searched for in the project root, in order of preference
+const DEFAULT_CONFIGS: [&str; 4] = ["launch.json", "launch.toml", "launch.yaml", "launch.yml"];
 
 /// Attempts to deserialize a config, searching for a default config if none is provided
 pub fn deserialize_config(path: &Option<PathBuf>) -> Result<Config> {
-    let path = {
-        let path = match &path {
-            Some(path) => Some(path.to_owned()),
-            None => search_default_config()?,
-        };
+    Config::deserialize(locate_config(path)?)
+}
 
-        match path {
-            Some(path) => path,
-            None => return Err(Error::NoConfig),
-        }
+/// Returns the provided config path, searching for a default config if none is provided
+pub fn locate_config(path: &Option<PathBuf>) -> Result<PathBuf> {
+    let path = match &path {
+        Some(path) => Some(path.to_owned()),
+        None => search_default_config()?,
     };
 
-    Config::deserialize(path)
+    path.ok_or(Error::NoConfig)
 }
 
 pub fn search_default_config() -> Result<Option<PathBuf>> {
-    for entry in fs::read_dir(util::locate_project_root()?)? {
-        if let Some(path) = try_get_config_entry(entry)? {
-            return Ok(Some(path));
-        }
-    }
-
-    Ok(None)
-}
+    let root = util::locate_project_root()?;
+    let config = DEFAULT_CONFIGS
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file());
 
-pub fn try_get_config_entry(entry: io::Result<DirEntry>) -> Result<Option<PathBuf>> {
-    let path = entry?.path();
-    match path.is_file() && util::path_to_string(&path)?.contains("launch.json") {
-        true => Ok(Some(path)),
-        false => Ok(None),
-    }
+    Ok(config)
 }
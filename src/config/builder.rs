@@ -0,0 +1,474 @@
+use super::{
+    collator::{CollatorNodeConfig, CollatorNodeRelayConfig},
+    validator::ValidatorNodeConfig,
+    CollatorConfig, Config, DevAccount, GenesisConfig, SpecPatch, ValidatorConfig,
+};
+use crate::{Error, PathBuffer, RestartPolicy, Result};
+use std::{collections::BTreeMap, time::Duration};
+
+// Name and account of the `index`th node added by count, e.g. `alice` running as alice, falling
+// back to `<prefix>-<n>` without an account once the development accounts run out
+fn dev_node(index: usize, prefix: &str) -> NodeBuilder {
+    match DevAccount::ALL.get(index) {
+        Some(account) => NodeBuilder::new(account.name()).dev_account(*account),
+        None => NodeBuilder::new(format!("{}-{}", prefix, index + 1)),
+    }
+}
+
+/// Builds a `Config`, starting with the network and adding a relay chain with its validators
+/// and parachains with their collators
+///
+/// Ports left unset are allocated when the config is launched.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    name: Option<String>,
+    author: Option<String>,
+    mode: Option<String>,
+    startup_timeout: Option<u64>,
+    min_peers: Option<usize>,
+    grace_period: Option<u64>,
+    port_base: Option<u16>,
+    workspace: Option<PathBuffer>,
+    cleanup: Option<bool>,
+    dev_accounts: Option<bool>,
+    env: BTreeMap<String, String>,
+    relay_chain: Option<RelayChainBuilder>,
+    parachains: Vec<ParachainBuilder>,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn author<S: Into<String>>(mut self, author: S) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn mode<S: Into<String>>(mut self, mode: S) -> Self {
+        self.mode = Some(mode.into());
+        self
+    }
+
+    pub fn startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = Some(timeout.as_secs());
+        self
+    }
+
+    pub fn min_peers(mut self, min_peers: usize) -> Self {
+        self.min_peers = Some(min_peers);
+        self
+    }
+
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = Some(grace_period.as_secs());
+        self
+    }
+
+    /// First port allocated to nodes without explicit ports
+    pub fn port_base(mut self, port_base: u16) -> Self {
+        self.port_base = Some(port_base);
+        self
+    }
+
+    /// Directory holding the chain data of every node, a temporary directory by default
+    pub fn workspace<P: Into<PathBuffer>>(mut self, workspace: P) -> Self {
+        self.workspace = Some(workspace.into());
+        self
+    }
+
+    /// Deletes the chain data of every node on shutdown, the default unless a workspace is set
+    pub fn cleanup(mut self, cleanup: bool) -> Self {
+        self.cleanup = Some(cleanup);
+        self
+    }
+
+    /// Assigns development accounts to nodes without one, in declaration order
+    pub fn dev_accounts(mut self, dev_accounts: bool) -> Self {
+        self.dev_accounts = Some(dev_accounts);
+        self
+    }
+
+    /// Sets an environment variable for every node
+    pub fn env<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn relay_chain(mut self, relay_chain: RelayChainBuilder) -> Self {
+        self.relay_chain = Some(relay_chain);
+        self
+    }
+
+    pub fn parachain(mut self, parachain: ParachainBuilder) -> Self {
+        self.parachains.push(parachain);
+        self
+    }
+
+    /// Builds and validates the config
+    pub fn build(self) -> Result<Config> {
+        let relay_chain = self
+            .relay_chain
+            .ok_or_else(|| Error::InvalidConfig("no relay chain".to_owned()))?;
+        if relay_chain.nodes.is_empty() {
+            return Err(Error::InvalidConfig(
+                "relay chain has no validators".to_owned(),
+            ));
+        }
+
+        let parachains = self
+            .parachains
+            .into_iter()
+            .map(|parachain| parachain.build(&relay_chain.chain))
+            .collect();
+
+        let mut config = Config {
+            name: self.name,
+            author: self.author,
+            mode: self.mode,
+            startup_timeout: self.startup_timeout,
+            min_peers: self.min_peers,
+            grace_period: self.grace_period,
+            port_base: self.port_base,
+            workspace: self.workspace,
+            cleanup: self.cleanup,
+            dev_accounts: self.dev_accounts,
+            env: self.env,
+            validator: relay_chain.build(),
+            parachains,
+            collator: None,
+            uses_collator: false,
+            unknown_fields: Vec::new(),
+        };
+        config.assign_dev_accounts();
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+/// Builds the relay chain of a config and its validators
+#[derive(Debug, Clone)]
+pub struct RelayChainBuilder {
+    bin: PathBuffer,
+    chain: PathBuffer,
+    dockerfile: Option<PathBuffer>,
+    session_keys: Option<PathBuffer>,
+    nodes: Vec<NodeBuilder>,
+}
+
+impl RelayChainBuilder {
+    /// Creates a relay chain run by `bin`, with the chain spec or built-in chain `chain`
+    pub fn new<B: Into<PathBuffer>, C: Into<PathBuffer>>(bin: B, chain: C) -> Self {
+        Self {
+            bin: bin.into(),
+            chain: chain.into(),
+            dockerfile: None,
+            session_keys: None,
+            nodes: Vec::new(),
+        }
+    }
+
+    pub fn dockerfile<P: Into<PathBuffer>>(mut self, dockerfile: P) -> Self {
+        self.dockerfile = Some(dockerfile.into());
+        self
+    }
+
+    /// Keys file written by `generate-relay-specs --validator-keys`, whose session keys are
+    /// inserted into the keystores of the validators on every launch
+    pub fn session_keys<P: Into<PathBuffer>>(mut self, session_keys: P) -> Self {
+        self.session_keys = Some(session_keys.into());
+        self
+    }
+
+    pub fn validator<N: Into<NodeBuilder>>(mut self, node: N) -> Self {
+        self.nodes.push(node.into());
+        self
+    }
+
+    /// Adds `count` validators named after and running as the development accounts, e.g.
+    /// `alice`
+    pub fn validators(mut self, count: usize) -> Self {
+        let start = self.nodes.len();
+        self.nodes
+            .extend((start..start + count).map(|index| dev_node(index, "validator")));
+        self
+    }
+
+    fn build(&self) -> ValidatorConfig {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| ValidatorNodeConfig {
+                name: node.name.to_owned(),
+                bin: node.bin.to_owned(),
+                dockerfile: None,
+                chain: self.chain.to_owned(),
+                args: node.args.to_owned(),
+                dev_account: node.dev_account,
+                seed: node.seed.to_owned(),
+                port: node.port,
+                ws_port: node.ws_port,
+                rpc_port: node.rpc_port,
+                restart: node.restart,
+            })
+            .collect();
+
+        ValidatorConfig {
+            bin: self.bin.to_owned(),
+            dockerfile: self.dockerfile.to_owned(),
+            session_keys: self.session_keys.to_owned(),
+            nodes,
+        }
+    }
+}
+
+/// Builds a parachain of a config and its collators
+#[derive(Debug, Clone)]
+pub struct ParachainBuilder {
+    name: Option<String>,
+    bin: PathBuffer,
+    chain: PathBuffer,
+    dockerfile: Option<PathBuffer>,
+    para_id: Option<u32>,
+    genesis: Option<GenesisConfig>,
+    patches: Vec<SpecPatch>,
+    nodes: Vec<NodeBuilder>,
+}
+
+impl ParachainBuilder {
+    /// Creates a parachain collated by `bin`, with the chain spec or built-in chain `chain`
+    pub fn new<B: Into<PathBuffer>, C: Into<PathBuffer>>(bin: B, chain: C) -> Self {
+        Self {
+            name: None,
+            bin: bin.into(),
+            chain: chain.into(),
+            dockerfile: None,
+            para_id: None,
+            genesis: None,
+            patches: Vec::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn para_id(mut self, para_id: u32) -> Self {
+        self.para_id = Some(para_id);
+        self
+    }
+
+    pub fn dockerfile<P: Into<PathBuffer>>(mut self, dockerfile: P) -> Self {
+        self.dockerfile = Some(dockerfile.into());
+        self
+    }
+
+    /// Genesis applied to the parachain's spec by `generate-specs`
+    pub fn genesis(mut self, genesis: GenesisConfig) -> Self {
+        self.genesis = Some(genesis);
+        self
+    }
+
+    /// Patch applied to the parachain's spec by `generate-specs`, after its genesis
+    pub fn patch(mut self, patch: SpecPatch) -> Self {
+        self.patches.push(patch);
+        self
+    }
+
+    pub fn collator<N: Into<NodeBuilder>>(mut self, node: N) -> Self {
+        self.nodes.push(node.into());
+        self
+    }
+
+    /// Adds `count` collators named after and running as the development accounts, e.g.
+    /// `alice`, prefixed with the parachain's name if it has one
+    pub fn collators(mut self, count: usize) -> Self {
+        let start = self.nodes.len();
+        let prefix = self.name.to_owned();
+        self.nodes.extend((start..start + count).map(|index| {
+            let node = dev_node(index, "collator");
+            match &prefix {
+                Some(prefix) => NodeBuilder {
+                    name: format!("{}-{}", prefix, node.name),
+                    ..node
+                },
+                None => node,
+            }
+        }));
+        self
+    }
+
+    // Collators connect to the relay chain through `relay_chain` unless they set their own
+    fn build(self, relay_chain: &PathBuffer) -> CollatorConfig {
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|node| CollatorNodeConfig {
+                name: node.name,
+                bin: node.bin,
+                dockerfile: None,
+                chain: None,
+                args: node.args,
+                dev_account: node.dev_account,
+                port: node.port,
+                ws_port: node.ws_port,
+                rpc_port: node.rpc_port,
+                restart: node.restart,
+                relay: CollatorNodeRelayConfig {
+                    chain: relay_chain.to_owned(),
+                    args: node.relay_args,
+                    port: node.relay_port,
+                    ws_port: node.relay_ws_port,
+                    rpc_port: node.relay_rpc_port,
+                },
+            })
+            .collect();
+
+        CollatorConfig {
+            name: self.name,
+            bin: self.bin,
+            dockerfile: self.dockerfile,
+            para_id: self.para_id,
+            chain: Some(self.chain),
+            genesis: self.genesis,
+            patches: self.patches,
+            nodes,
+        }
+    }
+}
+
+/// Builds a validator or collator node, the relay settings only apply to collators
+#[derive(Debug, Clone)]
+pub struct NodeBuilder {
+    name: String,
+    bin: Option<PathBuffer>,
+    args: Vec<String>,
+    dev_account: Option<DevAccount>,
+    seed: Option<String>,
+    port: Option<u16>,
+    ws_port: Option<u16>,
+    rpc_port: Option<u16>,
+    restart: RestartPolicy,
+    relay_args: Option<Vec<String>>,
+    relay_port: Option<u16>,
+    relay_ws_port: Option<u16>,
+    relay_rpc_port: Option<u16>,
+}
+
+impl NodeBuilder {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            bin: None,
+            args: Vec::new(),
+            dev_account: None,
+            seed: None,
+            port: None,
+            ws_port: None,
+            rpc_port: None,
+            restart: RestartPolicy::default(),
+            relay_args: None,
+            relay_port: None,
+            relay_ws_port: None,
+            relay_rpc_port: None,
+        }
+    }
+
+    /// Runs the node with `bin` instead of the binary of its chain
+    pub fn bin<P: Into<PathBuffer>>(mut self, bin: P) -> Self {
+        self.bin = Some(bin.into());
+        self
+    }
+
+    pub fn arg<S: Into<String>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Runs the node as the development account `account`, e.g. `--alice`
+    pub fn dev_account(mut self, account: DevAccount) -> Self {
+        self.dev_account = Some(account);
+        self
+    }
+
+    /// Derives the session keys of a validator from the secret URI `seed`, e.g. `//Alice`
+    pub fn seed<S: Into<String>>(mut self, seed: S) -> Self {
+        self.seed = Some(seed.into());
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn ws_port(mut self, ws_port: u16) -> Self {
+        self.ws_port = Some(ws_port);
+        self
+    }
+
+    pub fn rpc_port(mut self, rpc_port: u16) -> Self {
+        self.rpc_port = Some(rpc_port);
+        self
+    }
+
+    pub fn restart(mut self, restart: RestartPolicy) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Args passed to the embedded relay chain node of a collator
+    pub fn relay_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.relay_args = Some(args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn relay_port(mut self, port: u16) -> Self {
+        self.relay_port = Some(port);
+        self
+    }
+
+    pub fn relay_ws_port(mut self, ws_port: u16) -> Self {
+        self.relay_ws_port = Some(ws_port);
+        self
+    }
+
+    pub fn relay_rpc_port(mut self, rpc_port: u16) -> Self {
+        self.relay_rpc_port = Some(rpc_port);
+        self
+    }
+}
+
+impl From<&str> for NodeBuilder {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for NodeBuilder {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
@@ -1,70 +1,291 @@
 use crate::{
-    node::{BaseNode, Collator, CollatorRelay},
-    PathBuffer,
+    node::{BaseNode, Collator, CollatorRelay, Parachain},
+    port::PortAllocator,
+    Error, PathBuffer, RestartPolicy, Result,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use super::{
+    allocated, dev_account, resolve_chain, resolve_optional, DevAccount, GenesisConfig, Issues,
+    SpecPatch,
+};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CollatorConfig {
-    bin: PathBuffer,
-    dockerfile: Option<PathBuffer>,
-    nodes: Vec<CollatorNodeConfig>,
+    pub(super) name: Option<String>,
+    pub(super) bin: PathBuffer,
+    pub(super) dockerfile: Option<PathBuffer>,
+    pub(super) para_id: Option<u32>,
+    pub(super) chain: Option<PathBuffer>,
+    /// Genesis applied to the parachain's spec by `generate-specs`
+    pub(super) genesis: Option<GenesisConfig>,
+    /// Patches applied to the parachain's spec by `generate-specs`, after its genesis
+    #[serde(default)]
+    pub(super) patches: Vec<SpecPatch>,
+    pub(super) nodes: Vec<CollatorNodeConfig>,
+}
+
+impl CollatorConfig {
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    pub fn bin(&self) -> &PathBuffer {
+        &self.bin
+    }
+
+    /// Chain spec of the parachain, falling back to the one of its first node
+    pub fn chain(&self) -> Option<&PathBuffer> {
+        self.chain
+            .as_ref()
+            .or_else(|| self.nodes.first().and_then(|node| node.chain.as_ref()))
+    }
+
+    #[inline]
+    pub fn para_id(&self) -> Option<u32> {
+        self.para_id
+    }
+
+    #[inline]
+    pub fn genesis(&self) -> Option<&GenesisConfig> {
+        self.genesis.as_ref()
+    }
+
+    /// Applies the parachain's genesis, if it has one, to the plain chain spec `spec`
+    pub fn apply_genesis(&self, spec: &mut Value) -> Result<()> {
+        match &self.genesis {
+            Some(genesis) => {
+                let collators: Vec<_> = self.dev_accounts().into_iter().flatten().collect();
+                genesis.apply(spec, &collators)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the parachain's patches, in order, to the plain chain spec `spec`
+    pub fn apply_patches(&self, spec: &mut Value) -> Result<()> {
+        self.patches.iter().try_for_each(|patch| patch.apply(spec))
+    }
+
+    pub(super) fn resolve_paths(&mut self, base: &Path) -> Result<()> {
+        self.bin = self.bin.resolve_command(base)?;
+        self.dockerfile = resolve_optional(&self.dockerfile, base)?;
+        if let Some(chain) = &self.chain {
+            self.chain = Some(resolve_chain(chain, base)?);
+        }
+        for patch in self.patches.iter_mut() {
+            patch.resolve_paths(base)?;
+        }
+
+        for node in self.nodes.iter_mut() {
+            node.bin = node
+                .bin
+                .as_ref()
+                .map(|bin| bin.resolve_command(base))
+                .transpose()?;
+            node.dockerfile = resolve_optional(&node.dockerfile, base)?;
+            if let Some(chain) = &node.chain {
+                node.chain = Some(resolve_chain(chain, base)?);
+            }
+            node.relay.chain = resolve_chain(&node.relay.chain, base)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn ports(&self) -> impl Iterator<Item = u16> + '_ {
+        self.nodes
+            .iter()
+            .flat_map(|node| {
+                [
+                    node.port,
+                    node.ws_port,
+                    node.rpc_port,
+                    node.relay.port,
+                    node.relay.ws_port,
+                    node.relay.rpc_port,
+                ]
+            })
+            .flatten()
+    }
+
+    pub(super) fn allocate_ports(&mut self, ports: &mut PortAllocator) -> Result<()> {
+        for node in self.nodes.iter_mut() {
+            ports.fill(&mut node.port)?;
+            ports.fill(&mut node.ws_port)?;
+            ports.fill(&mut node.rpc_port)?;
+            ports.fill(&mut node.relay.port)?;
+            ports.fill(&mut node.relay.ws_port)?;
+            ports.fill(&mut node.relay.rpc_port)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn validate(&self, path: &str, issues: &mut Issues) {
+        issues.check_bin(&format!("{}.bin", path), &self.bin);
+        if let Some(chain) = &self.chain {
+            issues.check_spec(&format!("{}.chain", path), chain);
+        }
+        for (index, patch) in self.patches.iter().enumerate() {
+            if let SpecPatch::File(file) = patch {
+                if let Err(err) = SpecPatch::from_file(file.as_ref()) {
+                    issues.push(format!("{}.patches[{}].file", path, index), err.to_string());
+                }
+            }
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let path = format!("{}.nodes[{}]", path, index);
+            if let Some(bin) = &node.bin {
+                issues.check_bin(&format!("{}.bin", path), bin);
+            }
+
+            match (&node.chain, &self.chain) {
+                (Some(chain), _) => issues.check_spec(&format!("{}.chain", path), chain),
+                (None, Some(_)) => {}
+                (None, None) => issues.push(
+                    format!("{}.chain", path),
+                    "missing, and the parachain has no default chain",
+                ),
+            }
+            issues.check_spec(&format!("{}.relay.chain", path), &node.relay.chain);
+
+            if let Some(chain) = node.chain.as_ref().or(self.chain.as_ref()) {
+                dev_account::check_node(&path, node.dev_account, &node.args, chain, issues);
+            }
+        }
+    }
+
+    /// Warns about collators left out of the genesis for lack of a development account
+    pub(super) fn check_genesis_keys(&self, path: &str, warnings: &mut Issues) {
+        let collator_keys = match &self.genesis {
+            Some(genesis) => genesis.collator_keys.unwrap_or(true),
+            None => false,
+        };
+        if !collator_keys {
+            return;
+        }
+
+        for (index, account) in self.dev_accounts().iter().enumerate() {
+            if account.is_none() {
+                warnings.push(
+                    format!("{}.nodes[{}]", path, index),
+                    "has no development account, so its keys aren't added to the genesis",
+                );
+            }
+        }
+    }
+
+    /// Development account of every collator
+    pub(super) fn dev_accounts(&self) -> Vec<Option<DevAccount>> {
+        self.nodes
+            .iter()
+            .map(|node| dev_account::of_node(node.dev_account, &node.args))
+            .collect()
+    }
+
+    /// Assigns unused development accounts to the collators without one
+    pub(super) fn assign_dev_accounts(&mut self) {
+        let taken: Vec<_> = self.dev_accounts().into_iter().flatten().collect();
+        let mut unused = dev_account::unused(&taken);
+
+        for node in self.nodes.iter_mut() {
+            if dev_account::of_node(node.dev_account, &node.args).is_none() {
+                node.dev_account = unused.next();
+            }
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-struct CollatorNodeConfig {
-    name: String,
-    chain: PathBuffer,
-    args: Vec<String>,
-    port: u16,
-    ws_port: u16,
-    rpc_port: Option<u16>,
-    relay: CollatorNodeRelayConfig,
+pub(super) struct CollatorNodeConfig {
+    pub(super) name: String,
+    pub(super) bin: Option<PathBuffer>,
+    pub(super) dockerfile: Option<PathBuffer>,
+    pub(super) chain: Option<PathBuffer>,
+    pub(super) args: Vec<String>,
+    /// Development account the collator runs as, e.g. `alice` for `--alice`
+    pub(super) dev_account: Option<DevAccount>,
+    pub(super) port: Option<u16>,
+    pub(super) ws_port: Option<u16>,
+    pub(super) rpc_port: Option<u16>,
+    #[serde(default)]
+    pub(super) restart: RestartPolicy,
+    pub(super) relay: CollatorNodeRelayConfig,
 }
 
 impl CollatorNodeConfig {
-    fn base_node(&self, bin: &PathBuffer, dockerfile: &Option<PathBuffer>) -> BaseNode {
-        BaseNode::new(
+    // Node level `bin` and `dockerfile` take precedence over the parachain defaults
+    fn base_node(
+        &self,
+        bin: &PathBuffer,
+        chain: &PathBuffer,
+        dockerfile: &Option<PathBuffer>,
+    ) -> Result<BaseNode> {
+        Ok(BaseNode::new(
             self.name.to_owned(),
-            bin.clone(),
-            self.chain.clone(),
-            dockerfile.clone(),
-            self.args.to_owned(),
-            self.port.to_owned(),
-            self.ws_port.to_owned(),
-            self.rpc_port.to_owned(),
-        )
-    }
-
-    fn relay(&self) -> CollatorRelay {
-        CollatorRelay::new(
+            self.bin.as_ref().unwrap_or(bin).clone(),
+            chain.clone(),
+            self.dockerfile.as_ref().or(dockerfile.as_ref()).cloned(),
+            dev_account::node_args(self.dev_account, &self.args),
+            allocated(self.port, &self.name)?,
+            allocated(self.ws_port, &self.name)?,
+            allocated(self.rpc_port, &self.name)?,
+            self.restart,
+        ))
+    }
+
+    fn relay(&self) -> Result<CollatorRelay> {
+        Ok(CollatorRelay::new(
             self.relay.chain.to_owned(),
             self.relay.args.to_owned(),
-            self.relay.port,
-            self.relay.ws_port,
-            self.relay.rpc_port,
-        )
+            allocated(self.relay.port, &self.name)?,
+            allocated(self.relay.ws_port, &self.name)?,
+            allocated(self.relay.rpc_port, &self.name)?,
+        ))
     }
 
-    fn collator(&self, bin: &PathBuffer, dockerfile: &Option<PathBuffer>) -> Collator {
-        Collator::new(self.base_node(bin, dockerfile), self.relay())
+    // Falls back to the parachain's chain spec if the node doesn't specify one
+    fn collator(&self, config: &CollatorConfig) -> Result<Collator> {
+        let chain = match (&self.chain, &config.chain) {
+            (Some(chain), _) | (None, Some(chain)) => chain,
+            (None, None) => {
+                return Err(Error::InvalidConfig(format!(
+                    "collator {} has no chain spec",
+                    self.name
+                )))
+            }
+        };
+        let base_node = self.base_node(&config.bin, chain, &config.dockerfile)?;
+
+        Ok(Collator::new(base_node, self.relay()?))
     }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-struct CollatorNodeRelayConfig {
-    chain: PathBuffer,
-    args: Option<Vec<String>>,
-    port: u16,
-    ws_port: u16,
-    rpc_port: Option<u16>,
+pub(super) struct CollatorNodeRelayConfig {
+    pub(super) chain: PathBuffer,
+    pub(super) args: Option<Vec<String>>,
+    pub(super) port: Option<u16>,
+    pub(super) ws_port: Option<u16>,
+    pub(super) rpc_port: Option<u16>,
 }
 
-impl Into<Vec<Collator>> for CollatorConfig {
-    fn into(self) -> Vec<Collator> {
-        self.nodes
-            .into_iter()
-            .map(|validator_config| validator_config.collator(&self.bin, &self.dockerfile))
-            .collect()
+impl TryFrom<CollatorConfig> for Parachain {
+    type Error = Error;
+
+    fn try_from(config: CollatorConfig) -> Result<Self> {
+        let collators = config
+            .nodes
+            .iter()
+            .map(|node_config| node_config.collator(&config))
+            .collect::<Result<_>>()?;
+
+        Ok(Parachain::new(config.name, config.para_id, collators))
     }
 }
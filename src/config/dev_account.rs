@@ -0,0 +1,324 @@
+use super::Issues;
+use crate::PathBuffer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Pallets whose genesis config lists the authorities of a chain
+const AUTHORITY_PALLETS: [&str; 5] = ["session", "aura", "babe", "grandpa", "collatorSelection"];
+
+/// Storage prefixes of the authority pallets in raw chain specs, along with the well-known
+/// `:grandpa_authorities` key
+const AUTHORITY_PREFIXES: [&str; 6] = [
+    "0xcec5070d609dd3497f72bde07fc96ba0",
+    "0x57f8dc2f5ab09467896f47300f042438",
+    "0x1cb6f36e027abb2091cfb5110ab5087f",
+    "0x5f9cc45b7a00c5899361e1c6099678dc",
+    "0x15464cac3378d46f113cd5b7a4d71c84",
+    "0x3a6772616e6470615f617574686f726974696573",
+];
+
+/// Public key of a development account, as an SS58 address with the generic prefix and in hex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DevKey {
+    pub address: &'static str,
+    pub public: &'static str,
+}
+
+/// Well-known development accounts, e.g. `//Alice` of the development seed phrase, whose keys
+/// nodes insert into their keystore when started with `--alice`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DevAccount {
+    Alice,
+    Bob,
+    Charlie,
+    Dave,
+    Eve,
+    Ferdie,
+}
+
+impl DevAccount {
+    pub const ALL: [Self; 6] = [
+        Self::Alice,
+        Self::Bob,
+        Self::Charlie,
+        Self::Dave,
+        Self::Eve,
+        Self::Ferdie,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Alice => "alice",
+            Self::Bob => "bob",
+            Self::Charlie => "charlie",
+            Self::Dave => "dave",
+            Self::Eve => "eve",
+            Self::Ferdie => "ferdie",
+        }
+    }
+
+    /// Secret URI of the account, e.g. `//Alice`
+    pub fn seed(self) -> String {
+        let name = self.name();
+        format!("//{}{}", name[..1].to_uppercase(), &name[1..])
+    }
+
+    /// Node flag starting a node with the account's keys, e.g. `--alice`
+    pub fn flag(self) -> String {
+        format!("--{}", self.name())
+    }
+
+    /// The account found in `args` as a flag, if any
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|account| args.contains(&account.flag()))
+    }
+
+    /// Sr25519 key, the account ID and the Aura and BABE authority key
+    pub fn sr25519(self) -> DevKey {
+        let (address, public) = match self {
+            Self::Alice => (
+                "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+                "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",
+            ),
+            Self::Bob => (
+                "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty",
+                "8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48",
+            ),
+            Self::Charlie => (
+                "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y",
+                "90b5ab205c6974c9ea841be688864633dc9ca8a357843eeacf2314649965fe22",
+            ),
+            Self::Dave => (
+                "5DAAnrj7VHTznn2AWBemMuyBwZWs6FNFjdyVXUeYum3PTXFy",
+                "306721211d5404bd9da88e0204360a1a9ab8b87c66c1bc2fcdd37f3c2222cc20",
+            ),
+            Self::Eve => (
+                "5HGjWAeFDfFCWPsjFQdVV2Msvz2XtMktvgocEZcCj68kUMaw",
+                "e659a7a1628cdd93febc04a4e0646ea20e9f5f0ce097d9a05290d4a9e054df4e",
+            ),
+            Self::Ferdie => (
+                "5CiPPseXPECbkjWCa6MnjNokrgYjMqmKndv2rSnekmSK2DjL",
+                "1cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07c",
+            ),
+        };
+
+        DevKey { address, public }
+    }
+
+    /// Ed25519 key, the GRANDPA authority key
+    pub fn ed25519(self) -> DevKey {
+        let (address, public) = match self {
+            Self::Alice => (
+                "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu",
+                "88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee",
+            ),
+            Self::Bob => (
+                "5GoNkf6WdbxCFnPdAnYYQyCjAKPJgLNxXwPjwTh6DGg6gN3E",
+                "d17c2d7823ebf260fd138f2d7e27d114c0145d968b5ff5006125f2414fadae69",
+            ),
+            Self::Charlie => (
+                "5DbKjhNLpqX3zqZdNBc9BGb4fHU1cRBaDhJUskrvkwfraDi6",
+                "439660b36c6c03afafca027b910b4fecf99801834c62a5e6006f27d978de234f",
+            ),
+            Self::Dave => (
+                "5ECTwv6cZ5nJQPk6tWfaTrEk8YH2L7X1VT4EL5Tx2ikfFwb7",
+                "5e639b43e0052c47447dac87d6fd2b6ec50bdd4d0f614e4299c665249bbd09d9",
+            ),
+            Self::Eve => (
+                "5Ck2miBfCe1JQ4cY3NDsXyBaD6EcsgiVmEFTWwqNSs25XDEq",
+                "1dfe3e22cc0d45c70779c1095f7489a8ef3cf52d62fbd8c2fa38c9f1723502b5",
+            ),
+            Self::Ferdie => (
+                "5E2BmpVFzYGd386XRCZ76cDePMB3sfbZp5ZKGUsrG1m6gomN",
+                "568cb4a574c6d178feb39c27dfc8b3f789e5f5423e19c71633c748b9acf086b5",
+            ),
+        };
+
+        DevKey { address, public }
+    }
+
+    /// Whether the account is an authority in `spec`, i.e. one of its keys is listed by the
+    /// session, Aura, BABE, GRANDPA or collator selection pallet
+    pub fn is_authority(self, spec: &json::JsonValue) -> bool {
+        let keys = [self.sr25519(), self.ed25519()];
+        let genesis = &spec["genesis"];
+
+        if genesis["raw"].is_object() {
+            return genesis["raw"]["top"].entries().any(|(key, value)| {
+                let value = value.as_str().unwrap_or_default();
+                AUTHORITY_PREFIXES
+                    .iter()
+                    .any(|prefix| key.starts_with(prefix))
+                    && keys
+                        .iter()
+                        .any(|k| key.contains(k.public) || value.contains(k.public))
+            });
+        }
+
+        AUTHORITY_PALLETS
+            .iter()
+            .any(|pallet| lists_key(&genesis["runtime"][*pallet], &keys))
+    }
+}
+
+impl fmt::Display for DevAccount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Development accounts not in `taken`, in declaration order
+pub(super) fn unused(taken: &[DevAccount]) -> impl Iterator<Item = DevAccount> + '_ {
+    DevAccount::ALL
+        .into_iter()
+        .filter(move |account| !taken.contains(account))
+}
+
+/// Account a node runs as, set by its `dev_account` or by a flag in its `args`
+pub(super) fn of_node(dev_account: Option<DevAccount>, args: &[String]) -> Option<DevAccount> {
+    dev_account.or_else(|| DevAccount::from_args(args))
+}
+
+/// Args of a node, starting with the flag of its `dev_account` unless they contain it already
+pub(super) fn node_args(dev_account: Option<DevAccount>, args: &[String]) -> Vec<String> {
+    match dev_account {
+        Some(account) if !args.contains(&account.flag()) => {
+            let mut node_args = vec![account.flag()];
+            node_args.extend_from_slice(args);
+            node_args
+        }
+        _ => args.to_owned(),
+    }
+}
+
+/// Checks that the account of the node at `path` doesn't conflict with its `args` and is an
+/// authority in its chain spec `chain`
+pub(super) fn check_node(
+    path: &str,
+    dev_account: Option<DevAccount>,
+    args: &[String],
+    chain: &PathBuffer,
+    issues: &mut Issues,
+) {
+    if let (Some(account), Some(flagged)) = (dev_account, DevAccount::from_args(args)) {
+        if account != flagged {
+            issues.push(
+                format!("{}.dev_account", path),
+                format!("conflicts with {} in args", flagged.flag()),
+            );
+        }
+    }
+
+    if let Some(account) = of_node(dev_account, args) {
+        issues.check_authority(path, chain, account);
+    }
+}
+
+// Whether any string in `value` is the address or hex public key of one of `keys`
+fn lists_key(value: &json::JsonValue, keys: &[DevKey]) -> bool {
+    match value.as_str() {
+        Some(s) => keys
+            .iter()
+            .any(|key| s == key.address || s.strip_prefix("0x") == Some(key.public)),
+        None if value.is_array() => value.members().any(|member| lists_key(member, keys)),
+        None => value.entries().any(|(_, member)| lists_key(member, keys)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ValidatorConfig;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn flags_prefix_args_once() {
+        assert_eq!(
+            node_args(Some(DevAccount::Bob), &args(&["--validator"])),
+            ["--bob", "--validator"]
+        );
+        assert_eq!(
+            node_args(Some(DevAccount::Bob), &args(&["--bob"])),
+            ["--bob"]
+        );
+        assert_eq!(node_args(None, &args(&["--validator"])), ["--validator"]);
+    }
+
+    #[test]
+    fn dev_account_takes_precedence_over_flags() {
+        assert_eq!(
+            of_node(Some(DevAccount::Eve), &args(&["--alice"])),
+            Some(DevAccount::Eve)
+        );
+        assert_eq!(of_node(None, &args(&["--alice"])), Some(DevAccount::Alice));
+        assert_eq!(of_node(None, &args(&["--validator"])), None);
+    }
+
+    #[test]
+    fn unused_accounts_follow_declaration_order() {
+        let unused: Vec<_> = unused(&[DevAccount::Alice, DevAccount::Charlie]).collect();
+        assert_eq!(
+            unused,
+            [
+                DevAccount::Bob,
+                DevAccount::Dave,
+                DevAccount::Eve,
+                DevAccount::Ferdie
+            ]
+        );
+    }
+
+    #[test]
+    fn nodes_without_an_account_get_unused_ones() {
+        let mut config: ValidatorConfig = serde_json::from_str(
+            r#"{
+                "bin": "polkadot",
+                "nodes": [
+                    { "name": "a", "chain": "rococo-local", "args": [] },
+                    { "name": "b", "chain": "rococo-local", "args": ["--alice"] },
+                    { "name": "c", "chain": "rococo-local", "args": [], "dev_account": "bob" },
+                    { "name": "d", "chain": "rococo-local", "args": [] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        config.assign_dev_accounts();
+
+        assert_eq!(
+            config.dev_accounts(),
+            [
+                Some(DevAccount::Charlie),
+                Some(DevAccount::Alice),
+                Some(DevAccount::Bob),
+                Some(DevAccount::Dave)
+            ]
+        );
+    }
+
+    #[test]
+    fn authorities_are_found_in_plain_and_raw_specs() {
+        let alice = DevAccount::Alice;
+        let plain = json::parse(&format!(
+            r#"{{ "genesis": {{ "runtime": {{ "aura": {{ "authorities": ["{}"] }} }} }} }}"#,
+            alice.sr25519().address
+        ))
+        .unwrap();
+        let raw = json::parse(&format!(
+            r#"{{ "genesis": {{ "raw": {{ "top": {{ "{}": "0x04{}" }} }} }} }}"#,
+            AUTHORITY_PREFIXES[5],
+            alice.ed25519().public
+        ))
+        .unwrap();
+
+        assert!(alice.is_authority(&plain));
+        assert!(alice.is_authority(&raw));
+        assert!(!DevAccount::Bob.is_authority(&plain));
+        assert!(!DevAccount::Bob.is_authority(&raw));
+    }
+}
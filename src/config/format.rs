@@ -0,0 +1,171 @@
+use super::{validate, Config};
+use crate::error::{Error, Result, SerdeError};
+use serde::Deserializer;
+use std::path::Path;
+
+/// File formats a config can be written in, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Determines the format from the extension of `path`, defaulting to JSON
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path.as_ref().extension().and_then(|ext| ext.to_str());
+        match extension.map(str::to_lowercase).as_deref() {
+            Some("toml") => Self::Toml,
+            Some("yaml") | Some("yml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+
+    /// Deserializes a config, returning it along with the JSON paths of any unknown fields
+    pub(super) fn deserialize(&self, raw_config: &str) -> Result<(Config, Vec<String>)> {
+        match self {
+            Self::Json => deserialize_with(&mut serde_json::Deserializer::from_str(raw_config)),
+            Self::Toml => deserialize_with(&mut toml::Deserializer::new(raw_config)),
+            Self::Yaml => deserialize_with(serde_yaml::Deserializer::from_str(raw_config)),
+        }
+    }
+
+    pub(super) fn serialize(&self, config: &Config) -> Result<String> {
+        let serialized = match self {
+            Self::Json => serde_json::to_string_pretty(config).map_err(|err| err.to_string()),
+            // Converting to a `toml::Value` first orders plain values before tables
+            Self::Toml => toml::Value::try_from(config)
+                .and_then(|value| toml::to_string_pretty(&value))
+                .map_err(|err| err.to_string()),
+            Self::Yaml => serde_yaml::to_string(config).map_err(|err| err.to_string()),
+        };
+
+        serialized.map_err(|err| Error::Serde(SerdeError::Serialize(err)))
+    }
+}
+
+// Records unknown fields instead of ignoring them, they are reported by `Config::validate`
+fn deserialize_with<'de, D>(deserializer: D) -> Result<(Config, Vec<String>)>
+where
+    D: Deserializer<'de>,
+    D::Error: ToString,
+{
+    let mut unknown_fields = Vec::new();
+    let on_ignored = |path: serde_ignored::Path| unknown_fields.push(validate::json_path(&path));
+
+    match serde_ignored::deserialize(deserializer, on_ignored) {
+        Ok(config) => Ok((config, unknown_fields)),
+        Err(err) => Err(Error::Serde(SerdeError::Deserialize(err.to_string()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "name": "local",
+        "validator": {
+            "bin": "polkadot",
+            "nodes": [{
+                "name": "alice",
+                "chain": "rococo-local",
+                "args": ["--alice"],
+                "port": 30333,
+                "ws_port": 9944,
+                "restart": { "policy": "on_failure", "max_retries": 3 }
+            }]
+        },
+        "parachains": [{
+            "bin": "collator",
+            "para_id": 1000,
+            "chain": "specs/dev.json",
+            "patches": [
+                { "patch": [{ "op": "replace", "path": "/para_id", "value": 2000 }] },
+                { "merge": { "genesis": { "runtime": { "balances": { "existentialDeposit": 1.5 } } } } }
+            ],
+            "nodes": [{
+                "name": "collator",
+                "args": [],
+                "port": 30335,
+                "ws_port": 9946,
+                "relay": { "chain": "specs/rococo.json", "port": 30336, "ws_port": 9947 }
+            }]
+        }]
+    }"#;
+
+    fn json(config: &Config) -> String {
+        ConfigFormat::Json.serialize(config).unwrap()
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(ConfigFormat::from_path("launch.toml"), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path("launch.YML"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("launch.yaml"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("launch.json"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("launch"), ConfigFormat::Json);
+    }
+
+    #[test]
+    fn configs_round_trip_through_every_format() {
+        let (config, unknown_fields) = ConfigFormat::Json.deserialize(CONFIG).unwrap();
+        assert!(unknown_fields.is_empty());
+
+        for format in [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let serialized = format.serialize(&config).unwrap();
+            let (round_tripped, unknown_fields) = format.deserialize(&serialized).unwrap();
+
+            assert!(
+                unknown_fields.is_empty(),
+                "{:?}: {:?}",
+                format,
+                unknown_fields
+            );
+            assert_eq!(json(&round_tripped), json(&config), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn unknown_fields_are_collected_in_every_format() {
+        let toml = r#"
+            typo = 1
+            parachains = []
+
+            [validator]
+            bin = "polkadot"
+
+            [[validator.nodes]]
+            name = "alice"
+            chain = "rococo-local"
+            args = []
+            port = 30333
+            ws_port = 9944
+            wsport = 9944
+        "#;
+        let yaml = "
+            typo: 1
+            parachains: []
+            validator:
+              bin: polkadot
+              nodes:
+                - name: alice
+                  chain: rococo-local
+                  args: []
+                  port: 30333
+                  ws_port: 9944
+                  wsport: 9944
+        ";
+
+        for (format, raw_config) in [(ConfigFormat::Toml, toml), (ConfigFormat::Yaml, yaml)] {
+            let (_, unknown_fields) = format.deserialize(raw_config).unwrap();
+            assert_eq!(
+                unknown_fields,
+                ["$.typo", "$.validator.nodes[0].wsport"],
+                "{:?}",
+                format
+            );
+        }
+    }
+}
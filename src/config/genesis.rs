@@ -0,0 +1,231 @@
+use super::DevAccount;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// An account, either a development account such as `alice` or an SS58 address
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Account {
+    Dev(DevAccount),
+    Address(String),
+}
+
+impl Account {
+    pub fn address(&self) -> &str {
+        match self {
+            Self::Dev(account) => account.sr25519().address,
+            Self::Address(address) => address,
+        }
+    }
+}
+
+impl From<DevAccount> for Account {
+    fn from(account: DevAccount) -> Self {
+        Self::Dev(account)
+    }
+}
+
+/// An account endowed with `amount` at genesis
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Endowment {
+    pub account: Account,
+    pub amount: u128,
+}
+
+/// Genesis of a parachain, applied to its plain chain spec by `generate-specs`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GenesisConfig {
+    /// Accounts endowed at genesis, replacing those of the spec if any are given
+    #[serde(default)]
+    pub balances: Vec<Endowment>,
+    pub sudo: Option<Account>,
+    /// Whether the accounts of the collators replace the invulnerables, session keys and Aura
+    /// authorities of the spec, true by default
+    pub collator_keys: Option<bool>,
+}
+
+impl GenesisConfig {
+    /// Applies the genesis to the runtime section of the plain chain spec `spec`, with
+    /// `collators` being the accounts of the parachain's collators
+    pub fn apply(&self, spec: &mut Value, collators: &[DevAccount]) -> Result<()> {
+        let runtime = spec
+            .pointer_mut("/genesis/runtime")
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| Error::InvalidJsonValue("genesis.runtime".to_owned()))?;
+
+        if !self.balances.is_empty() {
+            let balances = self
+                .balances
+                .iter()
+                .map(|endowment| json!([endowment.account.address(), endowment.amount]))
+                .collect();
+            pallet(runtime, "balances")?.insert("balances".to_owned(), balances);
+        }
+
+        if let Some(sudo) = &self.sudo {
+            pallet(runtime, "sudo")?.insert("key".to_owned(), sudo.address().into());
+        }
+
+        if self.collator_keys.unwrap_or(true) && !collators.is_empty() {
+            set_collator_keys(runtime, collators)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Genesis config of the pallet `name`, which must be part of the runtime
+fn pallet<'a>(
+    runtime: &'a mut Map<String, Value>,
+    name: &str,
+) -> Result<&'a mut Map<String, Value>> {
+    match runtime.get_mut(name) {
+        Some(Value::Object(pallet)) => Ok(pallet),
+        Some(_) => Err(Error::InvalidJsonValue(format!("genesis.runtime.{}", name))),
+        None => Err(Error::InvalidConfig(format!(
+            "genesis sets {}, but the runtime has no such pallet",
+            name
+        ))),
+    }
+}
+
+// Makes `collators` the invulnerables and Aura authorities, through session keys if the runtime
+// has a session pallet, which then initializes Aura itself
+fn set_collator_keys(runtime: &mut Map<String, Value>, collators: &[DevAccount]) -> Result<()> {
+    let addresses = || collators.iter().map(|account| account.sr25519().address);
+
+    if runtime.contains_key("collatorSelection") {
+        pallet(runtime, "collatorSelection")?
+            .insert("invulnerables".to_owned(), addresses().collect());
+    }
+
+    match (
+        runtime.contains_key("session"),
+        runtime.contains_key("aura"),
+    ) {
+        (true, has_aura) => {
+            let keys = addresses()
+                .map(|address| json!([address, address, { "aura": address }]))
+                .collect();
+            pallet(runtime, "session")?.insert("keys".to_owned(), keys);
+            if has_aura {
+                pallet(runtime, "aura")?.insert("authorities".to_owned(), json!([]));
+            }
+        }
+        (false, true) => {
+            pallet(runtime, "aura")?.insert("authorities".to_owned(), addresses().collect());
+        }
+        (false, false) => return Err(Error::InvalidConfig(
+            "genesis sets collator keys, but the runtime has neither a session nor an aura pallet"
+                .to_owned(),
+        )),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util;
+
+    const COLLATORS: [DevAccount; 2] = [DevAccount::Alice, DevAccount::Bob];
+
+    // Plain spec whose runtime has the pallets `pallets`, each with an empty genesis config
+    fn spec(pallets: &[&str]) -> Value {
+        let runtime: Map<_, _> = pallets
+            .iter()
+            .map(|pallet| (pallet.to_string(), json!({})))
+            .collect();
+
+        json!({ "genesis": { "runtime": runtime } })
+    }
+
+    fn addresses() -> Vec<&'static str> {
+        COLLATORS
+            .iter()
+            .map(|account| account.sr25519().address)
+            .collect()
+    }
+
+    #[test]
+    fn collator_keys_go_to_session_if_present() {
+        let mut spec = spec(&["collatorSelection", "session", "aura"]);
+        GenesisConfig::default()
+            .apply(&mut spec, &COLLATORS)
+            .unwrap();
+
+        let runtime = &spec["genesis"]["runtime"];
+        let alice = addresses()[0];
+        assert_eq!(
+            runtime["collatorSelection"]["invulnerables"],
+            json!(addresses())
+        );
+        assert_eq!(runtime["session"]["keys"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            runtime["session"]["keys"][0],
+            json!([alice, alice, { "aura": alice }])
+        );
+        assert_eq!(runtime["aura"]["authorities"], json!([]));
+    }
+
+    #[test]
+    fn collator_keys_go_to_aura_without_session() {
+        let mut spec = spec(&["aura"]);
+        GenesisConfig::default()
+            .apply(&mut spec, &COLLATORS)
+            .unwrap();
+
+        let runtime = &spec["genesis"]["runtime"];
+        assert_eq!(runtime["aura"]["authorities"], json!(addresses()));
+        assert!(runtime.get("session").is_none());
+        assert!(runtime.get("collatorSelection").is_none());
+    }
+
+    #[test]
+    fn collator_keys_need_session_or_aura() {
+        let mut spec = spec(&["balances"]);
+        let result = GenesisConfig::default().apply(&mut spec, &COLLATORS);
+
+        assert!(matches!(result, Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn collator_keys_can_be_disabled() {
+        let mut spec = spec(&["balances"]);
+        let genesis = GenesisConfig {
+            collator_keys: Some(false),
+            ..GenesisConfig::default()
+        };
+
+        assert!(genesis.apply(&mut spec, &COLLATORS).is_ok());
+    }
+
+    #[test]
+    fn amounts_above_u64_are_written_in_full() {
+        let mut spec = spec(&["balances"]);
+        let genesis = GenesisConfig {
+            balances: vec![
+                Endowment {
+                    account: DevAccount::Alice.into(),
+                    amount: u128::MAX,
+                },
+                Endowment {
+                    account: DevAccount::Bob.into(),
+                    amount: 1000,
+                },
+            ],
+            collator_keys: Some(false),
+            ..GenesisConfig::default()
+        };
+        genesis.apply(&mut spec, &[]).unwrap();
+
+        let balances = &spec["genesis"]["runtime"]["balances"]["balances"];
+        assert_eq!(balances[1][1], 1000);
+
+        let written = util::pretty_json(&spec).unwrap();
+        assert!(written.contains(&format!("{}\n", u128::MAX)));
+        assert_eq!(util::parse_json(written.as_bytes()).unwrap(), spec);
+    }
+}
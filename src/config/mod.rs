@@ -1,20 +1,81 @@
-use crate::error::{Error, Result, SerdeError};
+use crate::{
+    error::{Error, Result},
+    port::{PortAllocator, DEFAULT_PORT_BASE},
+    PathBuffer,
+};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
+mod builder;
 mod collator;
+mod dev_account;
+mod format;
+mod genesis;
+mod patch;
+mod validate;
 mod validator;
 
+pub use builder::{ConfigBuilder, NodeBuilder, ParachainBuilder, RelayChainBuilder};
 pub use collator::CollatorConfig;
-pub use validator::ValidatorConfig;
+pub use dev_account::{DevAccount, DevKey};
+pub use format::ConfigFormat;
+pub use genesis::{Account, Endowment, GenesisConfig};
+pub use patch::{PatchOperation, SpecPatch};
+pub use validate::ConfigIssue;
+pub use validator::{ValidatorConfig, ValidatorSeed};
+
+use validate::Issues;
+
+// Resolves a chain spec path, leaving built-in chain ids untouched
+fn resolve_chain(chain: &PathBuffer, base: &Path) -> Result<PathBuffer> {
+    match validate::is_chain_id(chain) {
+        true => Ok(chain.clone()),
+        false => chain.resolve(base),
+    }
+}
+
+fn resolve_optional(path: &Option<PathBuffer>, base: &Path) -> Result<Option<PathBuffer>> {
+    path.as_ref().map(|path| path.resolve(base)).transpose()
+}
+
+// Unwraps a port filled in by `Config::allocate_ports`
+fn allocated(port: Option<u16>, node: &str) -> Result<u16> {
+    port.ok_or_else(|| Error::Uninitialized(format!("ports of node {}", node)))
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     pub name: Option<String>,
     pub author: Option<String>,
     pub mode: Option<String>,
+    pub startup_timeout: Option<u64>,
+    pub min_peers: Option<usize>,
+    pub grace_period: Option<u64>,
+    pub port_base: Option<u16>,
+    /// Directory holding the chain data of every node
+    pub workspace: Option<PathBuffer>,
+    /// Whether the chain data of every node is deleted on shutdown, by default only if no
+    /// `workspace` is set
+    pub cleanup: Option<bool>,
+    /// Whether development accounts are assigned to nodes without one, in declaration order
+    pub dev_accounts: Option<bool>,
+    /// Environment variables set for every node
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
     pub validator: ValidatorConfig,
-    pub collator: CollatorConfig,
+    #[serde(default)]
+    pub parachains: Vec<CollatorConfig>,
+    /// Single parachain of configs predating `parachains`, moved into it when parsed
+    #[serde(default, skip_serializing)]
+    collator: Option<CollatorConfig>,
+    #[serde(skip)]
+    uses_collator: bool,
+    #[serde(skip)]
+    unknown_fields: Vec<String>,
 }
 
 impl Config {
@@ -23,7 +84,7 @@ impl Config {
         author: Option<&str>,
         mode: Option<&str>,
         validator: ValidatorConfig,
-        collator: CollatorConfig,
+        parachains: Vec<CollatorConfig>,
     ) -> Self {
         let to_string = |value: &str| value.to_string();
 
@@ -31,16 +92,275 @@ impl Config {
             name: name.map(to_string),
             author: author.map(to_string),
             mode: mode.map(to_string),
+            startup_timeout: None,
+            min_peers: None,
+            grace_period: None,
+            port_base: None,
+            workspace: None,
+            cleanup: None,
+            dev_accounts: None,
+            env: BTreeMap::new(),
             validator,
-            collator,
+            parachains,
+            collator: None,
+            uses_collator: false,
+            unknown_fields: Vec::new(),
         }
     }
 
+    /// Deserializes the config at `path`, resolving the paths it contains relative to its
+    /// directory
     pub fn deserialize(path: PathBuf) -> Result<Self> {
-        let raw_config = &fs::read(path)?;
-        match serde_json::from_slice(raw_config) {
-            Ok(config) => Ok(config),
-            Err(err) => Err(Error::Serde(SerdeError::Deserialize(err.to_string()))),
+        let mut config = Self::parse(&path)?;
+        let path = fs::canonicalize(path)?;
+        config.resolve_paths(path.parent().ok_or(Error::InvalidPath)?)?;
+        config.assign_dev_accounts();
+
+        Ok(config)
+    }
+
+    #[inline]
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    /// Deserializes the config at `path` as is, in the format given by its extension
+    pub fn parse<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let raw_config = fs::read_to_string(&path)?;
+        let (mut config, unknown_fields) =
+            ConfigFormat::from_path(path).deserialize(&raw_config)?;
+
+        if let Some(collator) = config.collator.take() {
+            if !config.parachains.is_empty() {
+                return Err(Error::InvalidConfig(
+                    "both `collator` and `parachains` are set, move the collator into `parachains`"
+                        .to_owned(),
+                ));
+            }
+            config.parachains.push(collator);
+            config.uses_collator = true;
+        }
+
+        Ok(Self {
+            unknown_fields,
+            ..config
+        })
+    }
+
+    /// Serializes the config to `path`, in the format given by its extension
+    pub fn serialize<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let contents = ConfigFormat::from_path(&path).serialize(self)?;
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    /// Resolves every path in the config relative to `base`, expanding `~` and environment
+    /// variables
+    pub fn resolve_paths(&mut self, base: &Path) -> Result<()> {
+        self.workspace = resolve_optional(&self.workspace, base)?;
+        self.validator.resolve_paths(base)?;
+        self.parachains
+            .iter_mut()
+            .try_for_each(|parachain| parachain.resolve_paths(base))
+    }
+
+    /// Assigns a free port, starting at `port_base`, to every port left unset, skipping ports
+    /// set explicitly anywhere in the config and ports already in use on the host, and returns
+    /// the ports assigned
+    pub fn allocate_ports(&mut self) -> Result<Vec<u16>> {
+        let reserved = self
+            .validator
+            .ports()
+            .chain(
+                self.parachains
+                    .iter()
+                    .flat_map(|parachain| parachain.ports()),
+            )
+            .collect();
+        let mut ports = PortAllocator::new(self.port_base.unwrap_or(DEFAULT_PORT_BASE), reserved);
+
+        self.validator.allocate_ports(&mut ports)?;
+        self.parachains
+            .iter_mut()
+            .try_for_each(|parachain| parachain.allocate_ports(&mut ports))?;
+
+        Ok(ports.into_allocated())
+    }
+
+    /// The parachain with the id `para_id`
+    pub fn parachain(&self, para_id: u32) -> Option<&CollatorConfig> {
+        self.parachains
+            .iter()
+            .find(|parachain| parachain.para_id() == Some(para_id))
+    }
+
+    /// Session key seed of every validator
+    pub fn validator_seeds(&self) -> Vec<ValidatorSeed> {
+        self.validator.seeds()
+    }
+
+    /// Assigns unused development accounts to the nodes of every chain without one, if
+    /// `dev_accounts` is set
+    pub fn assign_dev_accounts(&mut self) {
+        if !self.dev_accounts.unwrap_or_default() {
+            return;
         }
+
+        self.validator.assign_dev_accounts();
+        self.parachains
+            .iter_mut()
+            .for_each(CollatorConfig::assign_dev_accounts);
+    }
+
+    /// Problems which don't prevent the config from being launched, e.g. nodes of a chain
+    /// sharing a development account
+    pub fn warnings(&self) -> Vec<ConfigIssue> {
+        let mut warnings = Issues::default();
+
+        if self.uses_collator {
+            warnings.push(
+                "$.collator",
+                "is deprecated, rename it to `parachains` and make it a list",
+            );
+        }
+
+        warnings.check_unique_accounts("$.validator.nodes", &self.validator.dev_accounts());
+        for (index, parachain) in self.parachains.iter().enumerate() {
+            let path = format!("$.parachains[{}]", index);
+            let nodes = format!("{}.nodes", path);
+            warnings.check_unique_accounts(&nodes, &parachain.dev_accounts());
+            parachain.check_genesis_keys(&path, &mut warnings);
+        }
+
+        warnings.into_vec()
+    }
+
+    /// Checks the config for unknown fields, missing binaries and invalid chain specs,
+    /// reporting every issue at once
+    pub fn validate(&self) -> Result<()> {
+        let mut issues = Issues::default();
+
+        for path in self.unknown_fields.iter() {
+            issues.push(path, "unknown field");
+        }
+
+        self.validator.validate("$.validator", &mut issues);
+        for (index, parachain) in self.parachains.iter().enumerate() {
+            parachain.validate(&format!("$.parachains[{}]", index), &mut issues);
+        }
+
+        issues.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, os::unix::fs::PermissionsExt, path::Path, process};
+
+    // Directory of the test `name` holding an executable `bin`, a non-executable `plain` file, a
+    // valid `spec.json` and an invalid `broken.json`
+    fn fixtures(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pendulum-launch-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("bin"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(dir.join("bin"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("plain"), "").unwrap();
+        fs::set_permissions(dir.join("plain"), fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(dir.join("spec.json"), "{}").unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+
+        dir
+    }
+
+    // Issues of `config`, written to `dir` with `$DIR` standing for `dir`
+    fn issues(dir: &Path, config: &str) -> Vec<String> {
+        let path = dir.join("launch.json");
+        fs::write(&path, config.replace("$DIR", dir.to_str().unwrap())).unwrap();
+
+        match Config::deserialize(path).unwrap().validate() {
+            Ok(()) => Vec::new(),
+            Err(Error::ConfigIssues(issues)) => issues.iter().map(ToString::to_string).collect(),
+            Err(err) => panic!("expected config issues, got {}", err),
+        }
+    }
+
+    #[test]
+    fn valid_config_has_no_issues() {
+        let dir = fixtures("valid-config");
+        let config = r#"{
+            "validator": {
+                "bin": "$DIR/bin",
+                "nodes": [
+                    { "name": "alice", "chain": "rococo-local", "args": [], "port": 1, "ws_port": 2 }
+                ]
+            },
+            "parachains": [{
+                "bin": "$DIR/bin",
+                "chain": "$DIR/spec.json",
+                "nodes": [{
+                    "name": "collator",
+                    "args": [],
+                    "port": 3,
+                    "ws_port": 4,
+                    "relay": { "chain": "$DIR/spec.json", "port": 5, "ws_port": 6 }
+                }]
+            }]
+        }"#;
+
+        assert_eq!(issues(&dir, config), Vec::<String>::new());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn every_issue_is_reported_with_its_path() {
+        let dir = fixtures("config-issues");
+        let config = r#"{
+            "nmae": "typo",
+            "validator": {
+                "bin": "$DIR/missing",
+                "nodes": [
+                    {
+                        "name": "alice",
+                        "bin": "$DIR/plain",
+                        "chain": "$DIR/broken.json",
+                        "args": [],
+                        "port": 1,
+                        "ws_port": 2,
+                        "wsport": 2
+                    },
+                    { "name": "bob", "bin": "$DIR", "chain": "rococo-local", "args": [], "port": 3, "ws_port": 4 }
+                ]
+            },
+            "parachains": [{
+                "bin": "$DIR/bin",
+                "nodes": [{
+                    "name": "collator",
+                    "args": [],
+                    "port": 5,
+                    "ws_port": 6,
+                    "restart": { "policy": "always", "bakcoff": 2 },
+                    "relay": { "chain": "$DIR/missing.json", "port": 7, "ws_port": 8 }
+                }]
+            }]
+        }"#;
+
+        assert_eq!(
+            issues(&dir, config),
+            [
+                "$.nmae: unknown field",
+                "$.validator.nodes[0].wsport: unknown field",
+                "$.parachains[0].nodes[0].restart.bakcoff: unknown field",
+                "$.validator.bin: does not exist",
+                "$.validator.nodes[0].bin: not executable",
+                "$.validator.nodes[0].chain: invalid chain spec: Unexpected end of JSON",
+                "$.validator.nodes[1].bin: not a file",
+                "$.parachains[0].nodes[0].chain: missing, and the parachain has no default chain",
+                "$.parachains[0].nodes[0].relay.chain: does not exist",
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,514 @@
+use crate::{Error, PathBuffer, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{fmt, fs, path::Path};
+
+// Result of patching, failing with a description of the offending path
+type PatchResult<T> = std::result::Result<T, String>;
+
+/// Edit of a plain chain spec, applied by `generate-specs`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "RawSpecPatch", into = "RawSpecPatch")]
+pub enum SpecPatch {
+    /// File holding a JSON Patch if it contains an array, a merge patch otherwise
+    File(PathBuffer),
+    /// RFC 6902 JSON Patch
+    Patch(Vec<PatchOperation>),
+    /// RFC 7386 merge patch
+    Merge(Value),
+}
+
+/// Operation of an RFC 6902 JSON Patch, locating values by RFC 6901 JSON pointers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PatchOperation {
+    Add {
+        path: String,
+        #[serde(serialize_with = "plain_value::serialize")]
+        value: Value,
+    },
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        #[serde(serialize_with = "plain_value::serialize")]
+        value: Value,
+    },
+    Move {
+        from: String,
+        path: String,
+    },
+    Copy {
+        from: String,
+        path: String,
+    },
+    Test {
+        path: String,
+        #[serde(serialize_with = "plain_value::serialize")]
+        value: Value,
+    },
+}
+
+// Patch as written in configs, a table with a single key since TOML has no enum variants
+// holding anything but a table
+#[derive(Default, Deserialize, Serialize)]
+struct RawSpecPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<PathBuffer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    patch: Option<Vec<PatchOperation>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "plain_value::serialize_option"
+    )]
+    merge: Option<Value>,
+}
+
+impl TryFrom<RawSpecPatch> for SpecPatch {
+    type Error = String;
+
+    fn try_from(raw: RawSpecPatch) -> std::result::Result<Self, Self::Error> {
+        match (raw.file, raw.patch, raw.merge) {
+            (Some(path), None, None) => Ok(Self::File(path)),
+            (None, Some(patch), None) => Ok(Self::Patch(patch)),
+            (None, None, Some(patch)) => Ok(Self::Merge(patch)),
+            _ => Err("expected exactly one of `file`, `patch` or `merge`".to_owned()),
+        }
+    }
+}
+
+impl From<SpecPatch> for RawSpecPatch {
+    fn from(patch: SpecPatch) -> Self {
+        match patch {
+            SpecPatch::File(path) => Self {
+                file: Some(path),
+                ..Self::default()
+            },
+            SpecPatch::Patch(patch) => Self {
+                patch: Some(patch),
+                ..Self::default()
+            },
+            SpecPatch::Merge(patch) => Self {
+                merge: Some(patch),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+impl SpecPatch {
+    /// Reads a patch file, a JSON Patch if it contains an array, a merge patch otherwise
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let invalid =
+            |err: &dyn fmt::Display| Error::InvalidPatch(format!("{}: {}", path.display(), err));
+
+        let raw = fs::read_to_string(path).map_err(|err| invalid(&err))?;
+        let patch: Value = serde_json::from_str(&raw).map_err(|err| invalid(&err))?;
+        match patch.is_array() {
+            true => serde_json::from_str(&raw)
+                .map(Self::Patch)
+                .map_err(|err| invalid(&err)),
+            false => Ok(Self::Merge(patch)),
+        }
+    }
+
+    pub fn apply(&self, spec: &mut Value) -> Result<()> {
+        match self {
+            Self::File(path) => {
+                Self::from_file(path.as_ref())?
+                    .apply(spec)
+                    .map_err(|err| match err {
+                        Error::InvalidPatch(msg) => {
+                            Error::InvalidPatch(format!("{}: {}", path.as_ref().display(), msg))
+                        }
+                        err => err,
+                    })
+            }
+            Self::Patch(operations) => {
+                operations
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(index, operation)| {
+                        operation.apply(spec).map_err(|msg| {
+                            Error::InvalidPatch(format!("operation {}: {}", index, msg))
+                        })
+                    })
+            }
+            Self::Merge(patch) => {
+                merge(spec, patch);
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn resolve_paths(&mut self, base: &Path) -> Result<()> {
+        if let Self::File(path) = self {
+            *path = path.resolve(base)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PatchOperation {
+    fn apply(&self, spec: &mut Value) -> PatchResult<()> {
+        match self {
+            Self::Add { path, value } => add(spec, path, value.clone()),
+            Self::Remove { path } => remove(spec, path).map(drop),
+            Self::Replace { path, value } => {
+                *pointer_mut(spec, path)? = value.clone();
+                Ok(())
+            }
+            Self::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(format!("cannot move {} into its own child {}", from, path));
+                }
+                let value = remove(spec, from)?;
+                add(spec, path, value)
+            }
+            Self::Copy { from, path } => {
+                let value = pointer(spec, from)?.clone();
+                add(spec, path, value)
+            }
+            Self::Test { path, value } => match pointer(spec, path)? == value {
+                true => Ok(()),
+                false => Err(format!("test failed, {} has a different value", path)),
+            },
+        }
+    }
+}
+
+// Serializes the values of inline patches with plain numbers, as the numbers of serde_json's
+// `arbitrary_precision` only serialize as numbers to serde_json itself, e.g. not to TOML
+mod plain_value {
+    use serde::{ser::Error, Serialize, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(value: &Value, serializer: S) -> Result<S::Ok, S::Error> {
+        Plain(value).serialize(serializer)
+    }
+
+    pub fn serialize_option<S: Serializer>(
+        value: &Option<Value>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.as_ref().map(Plain).serialize(serializer)
+    }
+
+    struct Plain<'a>(&'a Value);
+
+    impl Serialize for Plain<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self.0 {
+                Value::Number(number) => {
+                    let text = number.to_string();
+                    if let Ok(n) = text.parse::<u64>() {
+                        serializer.serialize_u64(n)
+                    } else if let Ok(n) = text.parse::<i64>() {
+                        serializer.serialize_i64(n)
+                    } else if let Ok(n) = text.parse::<u128>() {
+                        serializer.serialize_u128(n)
+                    } else if let Ok(n) = text.parse::<i128>() {
+                        serializer.serialize_i128(n)
+                    } else {
+                        let n = text.parse::<f64>().map_err(S::Error::custom)?;
+                        serializer.serialize_f64(n)
+                    }
+                }
+                Value::Array(array) => serializer.collect_seq(array.iter().map(Plain)),
+                Value::Object(object) => {
+                    serializer.collect_map(object.iter().map(|(key, value)| (key, Plain(value))))
+                }
+                value => value.serialize(serializer),
+            }
+        }
+    }
+}
+
+// Applies the RFC 7386 merge patch `patch` to `target`
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch.iter() {
+                match value.is_null() {
+                    true => drop(target.remove(key)),
+                    false => merge(target.entry(key.as_str()).or_insert(Value::Null), value),
+                }
+            }
+        }
+        (target, Value::Object(_)) => {
+            *target = Value::Object(Map::new());
+            merge(target, patch);
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+// Splits an RFC 6901 JSON pointer into its unescaped reference tokens
+fn tokens(pointer: &str) -> PatchResult<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    match pointer.strip_prefix('/') {
+        Some(tokens) => Ok(tokens
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect()),
+        None => Err(format!("invalid path {}, must start with /", pointer)),
+    }
+}
+
+// Index of an existing array element, which has no leading zeros
+fn index(token: &str, len: usize) -> Option<usize> {
+    let leading_zero = token.len() > 1 && token.starts_with('0');
+    match token.bytes().all(|b| b.is_ascii_digit()) && !leading_zero {
+        true => token.parse().ok().filter(|index| *index < len),
+        false => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, token: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Object(object) => object.get_mut(token),
+        Value::Array(array) => {
+            let index = index(token, array.len())?;
+            array.get_mut(index)
+        }
+        _ => None,
+    }
+}
+
+fn pointer<'a>(value: &'a Value, path: &str) -> PatchResult<&'a Value> {
+    tokens(path)?.iter().try_fold(value, |value, token| {
+        let child = match value {
+            Value::Object(object) => object.get(token),
+            Value::Array(array) => index(token, array.len()).and_then(|i| array.get(i)),
+            _ => None,
+        };
+        child.ok_or_else(|| format!("path {} does not exist", path))
+    })
+}
+
+fn pointer_mut<'a>(value: &'a mut Value, path: &str) -> PatchResult<&'a mut Value> {
+    tokens(path)?.iter().try_fold(value, |value, token| {
+        child_mut(value, token).ok_or_else(|| format!("path {} does not exist", path))
+    })
+}
+
+// Parent of the value at `path` along with the last token of `path`
+fn parent_mut<'a>(spec: &'a mut Value, path: &str) -> PatchResult<(&'a mut Value, String)> {
+    let mut tokens = tokens(path)?;
+    let last = tokens
+        .pop()
+        .ok_or_else(|| "the whole document can't be added to or removed".to_owned())?;
+
+    let parent = tokens.iter().try_fold(spec, |value, token| {
+        child_mut(value, token).ok_or_else(|| format!("parent of path {} does not exist", path))
+    })?;
+
+    Ok((parent, last))
+}
+
+fn add(spec: &mut Value, path: &str, value: Value) -> PatchResult<()> {
+    if path.is_empty() {
+        *spec = value;
+        return Ok(());
+    }
+
+    let (parent, token) = parent_mut(spec, path)?;
+    match parent {
+        Value::Object(object) => drop(object.insert(token, value)),
+        Value::Array(array) => {
+            let index = match token.as_str() {
+                "-" => array.len(),
+                token => index(token, array.len() + 1)
+                    .ok_or_else(|| format!("path {} is not a valid array index", path))?,
+            };
+            array.insert(index, value);
+        }
+        _ => return Err(format!("parent of path {} is not an object or array", path)),
+    }
+
+    Ok(())
+}
+
+fn remove(spec: &mut Value, path: &str) -> PatchResult<Value> {
+    let (parent, token) = parent_mut(spec, path)?;
+    let removed = match parent {
+        Value::Object(object) => object.remove(&token),
+        Value::Array(array) => index(&token, array.len()).map(|index| array.remove(index)),
+        _ => None,
+    };
+
+    removed.ok_or_else(|| format!("path {} does not exist", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec() -> Value {
+        json!({
+            "a/b": 1,
+            "c~d": 2,
+            "list": [0, 1, 2],
+            "object": { "key": "value", "nested": { "leaf": true } },
+        })
+    }
+
+    // Applies the JSON Patch `operations`, given as JSON, to `spec`
+    fn patch(spec: &mut Value, operations: &str) -> Result<()> {
+        SpecPatch::Patch(serde_json::from_str(operations).unwrap()).apply(spec)
+    }
+
+    fn error(result: Result<()>) -> String {
+        match result {
+            Err(Error::InvalidPatch(msg)) => msg,
+            other => panic!("expected an invalid patch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pointers_unescape_tokens() {
+        assert_eq!(tokens("/a~1b/c~0d").unwrap(), ["a/b", "c~d"]);
+        assert_eq!(tokens("/~01").unwrap(), ["~1"]);
+        assert!(tokens("").unwrap().is_empty());
+        assert!(tokens("a").is_err());
+
+        let mut spec = spec();
+        patch(
+            &mut spec,
+            r#"[
+                { "op": "replace", "path": "/a~1b", "value": 3 },
+                { "op": "remove", "path": "/c~0d" }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(spec["a/b"], 3);
+        assert!(spec.get("c~d").is_none());
+    }
+
+    #[test]
+    fn dash_appends_to_arrays() {
+        let mut spec = spec();
+        patch(
+            &mut spec,
+            r#"[{ "op": "add", "path": "/list/-", "value": 3 }]"#,
+        )
+        .unwrap();
+
+        assert_eq!(spec["list"], json!([0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn indices_have_no_leading_zeros() {
+        let mut spec = spec();
+        let replace = r#"[{ "op": "replace", "path": "/list/01", "value": 3 }]"#;
+        let add = r#"[{ "op": "add", "path": "/list/01", "value": 3 }]"#;
+
+        assert_eq!(
+            error(patch(&mut spec, replace)),
+            "operation 0: path /list/01 does not exist"
+        );
+        assert_eq!(
+            error(patch(&mut spec, add)),
+            "operation 0: path /list/01 is not a valid array index"
+        );
+        assert_eq!(spec["list"], json!([0, 1, 2]));
+    }
+
+    #[test]
+    fn values_cannot_move_into_their_own_child() {
+        let mut spec = spec();
+        let result = patch(
+            &mut spec,
+            r#"[{ "op": "move", "from": "/object", "path": "/object/nested/moved" }]"#,
+        );
+
+        assert_eq!(
+            error(result),
+            "operation 0: cannot move /object into its own child /object/nested/moved"
+        );
+        assert_eq!(spec, self::spec());
+    }
+
+    #[test]
+    fn missing_paths_are_reported() {
+        let mut spec = spec();
+        let result = patch(
+            &mut spec,
+            r#"[
+                { "op": "test", "path": "/list/0", "value": 0 },
+                { "op": "remove", "path": "/object/missing" }
+            ]"#,
+        );
+
+        assert_eq!(
+            error(result),
+            "operation 1: path /object/missing does not exist"
+        );
+    }
+
+    #[test]
+    fn merge_patches_remove_nulls() {
+        let mut spec = spec();
+        let merge: SpecPatch = serde_json::from_str(
+            r#"{ "merge": { "a/b": null, "object": { "key": null, "nested": { "added": 1 } } } }"#,
+        )
+        .unwrap();
+        merge.apply(&mut spec).unwrap();
+
+        assert!(spec.get("a/b").is_none());
+        assert_eq!(
+            spec["object"],
+            json!({ "nested": { "leaf": true, "added": 1 } })
+        );
+    }
+
+    #[test]
+    fn inline_patches_round_trip() {
+        let raw = r#"{"patch":[{"op":"add","path":"/list/-","value":{"big":[1,2.5,"x",null]}}]}"#;
+        let patch: SpecPatch = serde_json::from_str(raw).unwrap();
+
+        match &patch {
+            SpecPatch::Patch(operations) => match &operations[0] {
+                PatchOperation::Add { value, .. } => {
+                    assert_eq!(*value, json!({ "big": [1, 2.5, "x", null] }))
+                }
+                other => panic!("expected an add operation, got {:?}", other),
+            },
+            other => panic!("expected a JSON Patch, got {:?}", other),
+        }
+        assert_eq!(serde_json::to_string(&patch).unwrap(), raw);
+    }
+
+    #[test]
+    fn patches_take_exactly_one_key() {
+        let both = r#"{ "merge": {}, "file": "patch.json" }"#;
+        assert!(serde_json::from_str::<SpecPatch>(both).is_err());
+        assert!(serde_json::from_str::<SpecPatch>("{}").is_err());
+
+        let merge: SpecPatch = serde_json::from_str(r#"{ "merge": { "a": 1 } }"#).unwrap();
+        assert!(matches!(merge, SpecPatch::Merge(_)));
+    }
+
+    #[test]
+    fn numbers_keep_their_precision() {
+        let big = u128::MAX.to_string();
+        let mut spec = spec();
+        patch(
+            &mut spec,
+            &format!(
+                r#"[{{ "op": "replace", "path": "/a~1b", "value": {} }}]"#,
+                big
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(spec["a/b"].to_string(), big);
+    }
+}
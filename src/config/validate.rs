@@ -0,0 +1,154 @@
+use super::DevAccount;
+use crate::{Error, PathBuffer, Result};
+use std::{env, fmt, fs, io, os::unix::fs::PermissionsExt, path::PathBuf};
+
+/// A problem found in a config, located by the JSON path of the offending field
+#[derive(Debug)]
+pub struct ConfigIssue {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Collects every issue found while walking a config
+#[derive(Debug, Default)]
+pub(super) struct Issues(Vec<ConfigIssue>);
+
+impl Issues {
+    pub fn push<P: Into<String>, M: Into<String>>(&mut self, path: P, message: M) {
+        self.0.push(ConfigIssue {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Checks that `bin` is an existing, executable file, searching `PATH` for bare names
+    pub fn check_bin(&mut self, path: &str, bin: &PathBuffer) {
+        let bin = match bin.is_bare() {
+            true => match search_path(bin) {
+                Some(bin) => bin,
+                None => return self.push(path, "not found in PATH"),
+            },
+            false => bin.as_ref().to_owned(),
+        };
+
+        match fs::metadata(bin) {
+            Ok(metadata) if !metadata.is_file() => self.push(path, "not a file"),
+            Ok(metadata) if metadata.permissions().mode() & 0o111 == 0 => {
+                self.push(path, "not executable")
+            }
+            Ok(_) => {}
+            Err(err) => self.push(path, describe(err)),
+        }
+    }
+
+    /// Checks that `chain` is an existing chain spec containing valid JSON, unless it names a
+    /// built-in chain
+    pub fn check_spec(&mut self, path: &str, chain: &PathBuffer) {
+        if is_chain_id(chain) {
+            return;
+        }
+
+        match fs::read_to_string(chain.as_ref()) {
+            Ok(spec) => {
+                if let Err(err) = json::parse(&spec) {
+                    self.push(path, format!("invalid chain spec: {}", err));
+                }
+            }
+            Err(err) => self.push(path, describe(err)),
+        }
+    }
+
+    /// Checks that `account` is an authority in the chain spec `chain`, skipping built-in
+    /// chains and specs `check_spec` reports
+    pub fn check_authority(&mut self, path: &str, chain: &PathBuffer, account: DevAccount) {
+        if is_chain_id(chain) {
+            return;
+        }
+
+        let spec = match fs::read_to_string(chain.as_ref()).map(|spec| json::parse(&spec)) {
+            Ok(Ok(spec)) => spec,
+            _ => return,
+        };
+        if !account.is_authority(&spec) {
+            self.push(
+                path,
+                format!(
+                    "{} is not an authority in {}",
+                    account,
+                    chain.as_ref().display()
+                ),
+            );
+        }
+    }
+
+    /// Checks that no two of `accounts`, those of the nodes at `<path>[<index>]`, are the same
+    pub fn check_unique_accounts(&mut self, path: &str, accounts: &[Option<DevAccount>]) {
+        for (index, account) in accounts.iter().enumerate() {
+            let first = accounts.iter().position(|other| other == account);
+            if let (Some(account), Some(first)) = (account, first.filter(|first| *first < index)) {
+                self.push(
+                    format!("{}[{}]", path, index),
+                    format!("shares the account {} with {}[{}]", account, path, first),
+                );
+            }
+        }
+    }
+
+    #[inline]
+    pub fn into_vec(self) -> Vec<ConfigIssue> {
+        self.0
+    }
+
+    pub fn into_result(self) -> Result<()> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(Error::ConfigIssues(self.0)),
+        }
+    }
+}
+
+// First file named `name` in the directories of `PATH`
+fn search_path(name: &PathBuffer) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name.as_ref()))
+        .find(|path| path.is_file())
+}
+
+fn describe(err: io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::NotFound => "does not exist".to_owned(),
+        _ => err.to_string(),
+    }
+}
+
+/// Whether `chain` names a chain built into the node binary, e.g. `rococo-local`, rather than a
+/// chain spec file
+pub(super) fn is_chain_id(chain: &PathBuffer) -> bool {
+    let path = chain.as_ref();
+    let bare = match path.parent() {
+        Some(parent) => parent.as_os_str().is_empty(),
+        None => true,
+    };
+
+    bare && path.extension().is_none()
+}
+
+/// Formats a path reported by `serde_ignored` as a JSON path
+pub(super) fn json_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+
+    match path {
+        Path::Root => "$".to_owned(),
+        Path::Seq { parent, index } => format!("{}[{}]", json_path(parent), index),
+        Path::Map { parent, key } => format!("{}.{}", json_path(parent), key),
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => json_path(parent),
+    }
+}
@@ -1,47 +1,202 @@
 use crate::{
     node::{BaseNode, Validator},
-    PathBuffer,
+    port::PortAllocator,
+    workspace, Error, KeysFile, PathBuffer, RestartPolicy, Result,
 };
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::{allocated, dev_account, resolve_chain, resolve_optional, DevAccount, Issues};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ValidatorConfig {
-    bin: PathBuffer,
-    dockerfile: Option<PathBuffer>,
-    nodes: Vec<ValidatorNodeConfig>,
+    pub(super) bin: PathBuffer,
+    pub(super) dockerfile: Option<PathBuffer>,
+    /// Keys file written by `generate-relay-specs --validator-keys`, whose session keys are
+    /// inserted into the keystores of the validators on every launch
+    pub(super) session_keys: Option<PathBuffer>,
+    pub(super) nodes: Vec<ValidatorNodeConfig>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-struct ValidatorNodeConfig {
-    name: String,
-    chain: PathBuffer,
-    args: Vec<String>,
-    port: u16,
-    ws_port: u16,
-    rpc_port: Option<u16>,
+pub(super) struct ValidatorNodeConfig {
+    pub(super) name: String,
+    pub(super) bin: Option<PathBuffer>,
+    pub(super) dockerfile: Option<PathBuffer>,
+    pub(super) chain: PathBuffer,
+    pub(super) args: Vec<String>,
+    /// Development account the node runs as, e.g. `alice` for `--alice`
+    pub(super) dev_account: Option<DevAccount>,
+    /// Secret URI the node's session keys are derived from by `generate-relay-specs`, that of
+    /// its development account or `//<name>` by default
+    pub(super) seed: Option<String>,
+    pub(super) port: Option<u16>,
+    pub(super) ws_port: Option<u16>,
+    pub(super) rpc_port: Option<u16>,
+    #[serde(default)]
+    pub(super) restart: RestartPolicy,
+}
+
+/// Session key seed of a validator, along with the binary deriving its keys
+#[derive(Debug, Clone)]
+pub struct ValidatorSeed {
+    pub name: String,
+    /// Secret URI, e.g. `//Alice`
+    pub seed: String,
+    pub bin: PathBuffer,
 }
 
 impl ValidatorNodeConfig {
-    fn base_node(&self, bin: &PathBuffer, dockerfile: &Option<PathBuffer>) -> BaseNode {
-        BaseNode::new(
+    // The configured seed, falling back to that of the node's development account
+    fn seed(&self) -> String {
+        if let Some(seed) = &self.seed {
+            return seed.to_owned();
+        }
+
+        match dev_account::of_node(self.dev_account, &self.args) {
+            Some(account) => account.seed(),
+            None => format!("//{}", self.name),
+        }
+    }
+
+    // Node level `bin` and `dockerfile` take precedence over the group defaults
+    fn base_node(&self, bin: &PathBuffer, dockerfile: &Option<PathBuffer>) -> Result<BaseNode> {
+        Ok(BaseNode::new(
             self.name.to_owned(),
-            bin.clone(),
+            self.bin.as_ref().unwrap_or(bin).clone(),
             self.chain.to_owned(),
-            dockerfile.clone(),
-            self.args.to_owned(),
-            self.port.to_owned(),
-            self.ws_port.to_owned(),
-            self.rpc_port.to_owned(),
-        )
+            self.dockerfile.as_ref().or(dockerfile.as_ref()).cloned(),
+            dev_account::node_args(self.dev_account, &self.args),
+            allocated(self.port, &self.name)?,
+            allocated(self.ws_port, &self.name)?,
+            allocated(self.rpc_port, &self.name)?,
+            self.restart,
+        ))
     }
 }
 
-impl Into<Vec<Validator>> for ValidatorConfig {
-    fn into(self) -> Vec<Validator> {
+impl ValidatorConfig {
+    #[inline]
+    pub fn bin(&self) -> &PathBuffer {
+        &self.bin
+    }
+
+    pub(super) fn resolve_paths(&mut self, base: &Path) -> Result<()> {
+        self.bin = self.bin.resolve_command(base)?;
+        self.dockerfile = resolve_optional(&self.dockerfile, base)?;
+        self.session_keys = resolve_optional(&self.session_keys, base)?;
+
+        for node in self.nodes.iter_mut() {
+            node.bin = node
+                .bin
+                .as_ref()
+                .map(|bin| bin.resolve_command(base))
+                .transpose()?;
+            node.dockerfile = resolve_optional(&node.dockerfile, base)?;
+            node.chain = resolve_chain(&node.chain, base)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn ports(&self) -> impl Iterator<Item = u16> + '_ {
+        self.nodes
+            .iter()
+            .flat_map(|node| [node.port, node.ws_port, node.rpc_port])
+            .flatten()
+    }
+
+    pub(super) fn allocate_ports(&mut self, ports: &mut PortAllocator) -> Result<()> {
+        for node in self.nodes.iter_mut() {
+            ports.fill(&mut node.port)?;
+            ports.fill(&mut node.ws_port)?;
+            ports.fill(&mut node.rpc_port)?;
+        }
+
+        Ok(())
+    }
+
+    /// Session key seed of every node
+    pub(super) fn seeds(&self) -> Vec<ValidatorSeed> {
         self.nodes
-            .into_iter()
-            .map(|validator_config| {
-                Validator::new(validator_config.base_node(&self.bin, &self.dockerfile))
+            .iter()
+            .map(|node| ValidatorSeed {
+                name: node.name.to_owned(),
+                seed: node.seed(),
+                bin: node.bin.as_ref().unwrap_or(&self.bin).clone(),
+            })
+            .collect()
+    }
+
+    /// Development account of every node
+    pub(super) fn dev_accounts(&self) -> Vec<Option<DevAccount>> {
+        self.nodes
+            .iter()
+            .map(|node| dev_account::of_node(node.dev_account, &node.args))
+            .collect()
+    }
+
+    /// Assigns unused development accounts to the nodes without one
+    pub(super) fn assign_dev_accounts(&mut self) {
+        let taken: Vec<_> = self.dev_accounts().into_iter().flatten().collect();
+        let mut unused = dev_account::unused(&taken);
+
+        for node in self.nodes.iter_mut() {
+            if dev_account::of_node(node.dev_account, &node.args).is_none() {
+                node.dev_account = unused.next();
+            }
+        }
+    }
+
+    pub(super) fn validate(&self, path: &str, issues: &mut Issues) {
+        issues.check_bin(&format!("{}.bin", path), &self.bin);
+        let keys = match &self.session_keys {
+            Some(session_keys) => match KeysFile::read(session_keys.as_ref()) {
+                Ok(keys) => Some(keys),
+                Err(err) => {
+                    issues.push(format!("{}.session_keys", path), err.to_string());
+                    None
+                }
+            },
+            None => None,
+        };
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let path = format!("{}.nodes[{}]", path, index);
+            if let Some(bin) = &node.bin {
+                issues.check_bin(&format!("{}.bin", path), bin);
+            }
+            issues.check_spec(&format!("{}.chain", path), &node.chain);
+            dev_account::check_node(&path, node.dev_account, &node.args, &node.chain, issues);
+            let has_keys = keys
+                .as_ref()
+                .is_some_and(|keys| keys.validator(&node.name).is_some());
+            if has_keys && workspace::runs_with_tmp(&node.args) {
+                issues.push(
+                    format!("{}.args", path),
+                    "runs with --tmp, so its session keys can't be inserted",
+                );
+            }
+        }
+    }
+}
+
+impl TryFrom<ValidatorConfig> for Vec<Validator> {
+    type Error = Error;
+
+    fn try_from(config: ValidatorConfig) -> Result<Self> {
+        let keys = match &config.session_keys {
+            Some(path) => KeysFile::read(path.as_ref())?,
+            None => KeysFile::default(),
+        };
+
+        config
+            .nodes
+            .iter()
+            .map(|node_config| {
+                let base_node = node_config.base_node(&config.bin, &config.dockerfile)?;
+                let keystore = keys.validator(&node_config.name).cloned();
+                Ok(Validator::new(base_node, keystore))
             })
             .collect()
     }
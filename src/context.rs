@@ -0,0 +1,92 @@
+use crate::{
+    logs,
+    task::{LogFilter, OutputTarget, StderrLog},
+    PathBuffer, Result, Workspace,
+};
+use std::{
+    collections::BTreeMap,
+    process::{Command, Stdio},
+};
+
+/// Default number of run directories kept in the log directory
+pub const DEFAULT_KEEP_RUNS: usize = 10;
+
+/// Default size in bytes at which node logs are rotated
+pub const DEFAULT_MAX_LOG_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Where the output of node processes goes
+#[derive(Debug, Clone)]
+pub struct NodeOutput {
+    /// Directory of per-run log directories, output is shown in the terminal if unset
+    pub log_dir: Option<PathBuffer>,
+    pub stderr: StderrLog,
+    /// Number of run directories kept in `log_dir`
+    pub keep_runs: usize,
+    /// Size in bytes at which node logs are rotated
+    pub max_log_size: u64,
+    /// Output shown in the terminal
+    pub filter: LogFilter,
+    /// Discards both streams
+    pub quiet: bool,
+}
+
+impl Default for NodeOutput {
+    fn default() -> Self {
+        Self {
+            log_dir: None,
+            stderr: StderrLog::default(),
+            keep_runs: DEFAULT_KEEP_RUNS,
+            max_log_size: DEFAULT_MAX_LOG_SIZE,
+            filter: LogFilter::default(),
+            quiet: false,
+        }
+    }
+}
+
+impl NodeOutput {
+    // Where node output is streamed to, creating a new run directory when logging to files
+    pub(crate) fn target(&self) -> Result<Option<OutputTarget>> {
+        let target = match (&self.log_dir, self.quiet) {
+            (_, true) => None,
+            (Some(log_dir), false) => {
+                let dir = logs::create_run_dir(log_dir, self.keep_runs)?;
+                println!("Logging to {}", dir.to_string()?);
+
+                Some(OutputTarget::Files {
+                    dir,
+                    stderr: self.stderr,
+                    max_size: self.max_log_size,
+                })
+            }
+            (None, false) => Some(OutputTarget::Terminal(self.filter.clone())),
+        };
+
+        Ok(target)
+    }
+}
+
+/// Settings shared by every node of a launcher, so independent networks can run side by side
+/// in one process
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    pub output: NodeOutput,
+    /// Environment variables set for every node
+    pub env: BTreeMap<String, String>,
+    pub workspace: Workspace,
+}
+
+impl Context {
+    /// Creates a command running `bin` in the context's environment, piping its
+    /// output to be streamed by the task manager unless it's discarded
+    pub fn command(&self, bin: &PathBuffer) -> Command {
+        let output = || match self.output.quiet {
+            true => Stdio::null(),
+            false => Stdio::piped(),
+        };
+
+        let mut command = Command::new(bin.as_ref());
+        command.envs(&self.env).stdout(output()).stderr(output());
+
+        command
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config::ConfigIssue;
 use serde::{de, ser};
 use std::{error, fmt, io, result, string, sync::PoisonError};
 use thiserror::Error;
@@ -8,16 +9,32 @@ pub type Result<T> = result::Result<T, Error>;
 pub enum Error {
     #[error("Must provide a config")]
     NoConfig,
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid config:\n{}", format_issues(.0))]
+    ConfigIssues(Vec<ConfigIssue>),
     #[error("Must provide valid path")]
     InvalidPath,
     #[error("Port {0} used more than once")]
     PortInUse(u16),
+    #[error("Port {port} of node {node} is already bound{}", format_pid(.pid))]
+    PortBound {
+        node: String,
+        port: u16,
+        pid: Option<u32>,
+    },
+    #[error("No free port left at or above {0}")]
+    NoFreePort(u16),
     #[error("Uninitialized: {0}")]
     Uninitialized(String),
     #[error("Process failed: {0}")]
     ProcessFailed(String),
+    #[error("Startup timed out: {0}")]
+    StartupTimeout(String),
     #[error("Invalid json value: {0}")]
     InvalidJsonValue(String),
+    #[error("Invalid patch: {0}")]
+    InvalidPatch(String),
     #[error("Lock poisoned {0}")]
     Poison(String),
     #[error(transparent)]
@@ -25,13 +42,27 @@ pub enum Error {
     #[error(transparent)]
     Ctrlc(#[from] ctrlc::Error),
     #[error(transparent)]
+    Nix(#[from] nix::Error),
+    #[error(transparent)]
     ParseJson(#[from] json::Error),
     #[error(transparent)]
     FromUtf8(#[from] string::FromUtf8Error),
     #[error(transparent)]
     Serde(#[from] SerdeError),
     #[error(transparent)]
-    Other(#[from] Box<dyn error::Error>),
+    Other(#[from] Box<dyn error::Error + Send + Sync>),
+}
+
+fn format_issues(issues: &[ConfigIssue]) -> String {
+    let issues: Vec<String> = issues.iter().map(|issue| format!("  {}", issue)).collect();
+    issues.join("\n")
+}
+
+fn format_pid(pid: &Option<u32>) -> String {
+    match pid {
+        Some(pid) => format!(" by process {}", pid),
+        None => String::new(),
+    }
 }
 
 impl<T> From<PoisonError<T>> for Error {
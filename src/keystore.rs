@@ -0,0 +1,79 @@
+use crate::{error::SerdeError, util, Context, Error, PathBuffer, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Session keys of the validators of a relay chain spec, written next to it by
+/// `generate-relay-specs --validator-keys` and inserted into the keystores of the validators on
+/// every launch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeysFile {
+    pub validators: Vec<ValidatorKeystore>,
+}
+
+/// Session keys of a validator, all derived from its seed
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ValidatorKeystore {
+    pub name: String,
+    /// Secret URI, e.g. `//Alice`
+    pub seed: String,
+    pub keys: Vec<KeystoreKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeystoreKey {
+    /// Four letter key type, e.g. `gran`
+    pub key_type: String,
+    /// Signature scheme, e.g. `ed25519`
+    pub scheme: String,
+}
+
+impl KeysFile {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+
+        serde_json::from_str(&contents)
+            .map_err(|err| Error::Serde(SerdeError::Deserialize(err.to_string())))
+    }
+
+    /// Writes the keys file to `path` as JSON
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|err| Error::Serde(SerdeError::Serialize(err.to_string())))?;
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    /// Session keys of the validator named `name`
+    pub fn validator(&self, name: &str) -> Option<&ValidatorKeystore> {
+        self.validators
+            .iter()
+            .find(|validator| validator.name == name)
+    }
+}
+
+impl ValidatorKeystore {
+    /// Inserts the session keys into the keystore of the `chain` in `base_path` with the
+    /// `key insert` command of `bin`
+    pub fn insert(
+        &self,
+        context: &Context,
+        bin: &PathBuffer,
+        chain: &PathBuffer,
+        base_path: &PathBuffer,
+    ) -> Result<()> {
+        self.keys.iter().try_for_each(|key| {
+            let output = context
+                .command(bin)
+                .args(["key", "insert", "--base-path"])
+                .arg(base_path.as_ref())
+                .arg("--chain")
+                .arg(chain.as_ref())
+                .args(["--scheme", &key.scheme, "--key-type", &key.key_type])
+                .args(["--suri", &self.seed])
+                .output()?;
+
+            util::ensure_success(&output)
+        })
+    }
+}
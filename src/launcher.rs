@@ -1,18 +1,17 @@
 use crate::{
-    node::{Collator, Node, Validator},
-    task::{Task, TaskManager},
-    Config, Error, PathBuffer, Result,
-};
-use lazy_static::lazy_static;
-use std::{
-    collections::HashSet,
-    path::PathBuf,
-    sync::{Arc, RwLock},
+    node::{Collator, Node, Parachain, Validator},
+    port::{self, PortMap},
+    readiness::{self, Probe},
+    task::{Phase, TaskManager},
+    Config, Context, Error, Network, NodeOutput, Result, Workspace,
 };
+use std::{collections::HashSet, time::Duration};
 
-lazy_static! {
-    pub(crate) static ref LOG_DIR: Arc<RwLock<Option<PathBuffer>>> = Arc::new(RwLock::new(None));
-}
+/// Default time each startup phase is given to become ready
+const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Default time nodes are given to exit after SIGTERM before being killed
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(10);
 
 #[derive(Debug)]
 pub enum LauncherMode {
@@ -45,43 +44,168 @@ pub struct Launcher {
     pub name: Option<String>,
     pub author: Option<String>,
     pub mode: LauncherMode,
+    pub startup_timeout: Duration,
+    pub min_peers: usize,
+    pub grace_period: Duration,
     pub validators: Vec<Validator>,
-    pub collators: Vec<Collator>,
+    pub parachains: Vec<Parachain>,
+    pub context: Context,
+    /// Ports allocated to nodes without explicit ports, released when the network started with
+    /// them is dropped
+    pub allocated_ports: Vec<u16>,
 }
 
 impl<'a> Launcher {
-    pub fn new(config: Config, log_dir: Option<PathBuf>) -> Result<Self> {
-        // Initialize LOG_DIR
-        *Arc::clone(&LOG_DIR).write()? = log_dir.map(PathBuffer::from);
+    pub fn new(mut config: Config, output: NodeOutput) -> Result<Self> {
+        let allocated_ports = config.allocate_ports()?;
+
+        let workspace = Workspace::of_config(&config);
+
+        let context = Context {
+            output,
+            env: config.env.to_owned(),
+            workspace,
+        };
 
         let name = config.name.to_owned();
         let author = config.author.to_owned();
         let mode = LauncherMode::from(config.mode);
-        let validators = config.validator.into();
-        let collators = config.collator.into();
+        let startup_timeout = config
+            .startup_timeout
+            .map_or(DEFAULT_STARTUP_TIMEOUT, Duration::from_secs);
+        let min_peers = config.min_peers.unwrap_or_default();
+        let grace_period = config
+            .grace_period
+            .map_or(DEFAULT_GRACE_PERIOD, Duration::from_secs);
+        let mut validators = Vec::try_from(config.validator)?;
+        let mut parachains: Vec<_> = config
+            .parachains
+            .into_iter()
+            .map(Parachain::try_from)
+            .collect::<Result<_>>()?;
+        connect_nodes(&mut validators, &mut parachains);
 
         Ok(Self {
             name,
             author,
             mode,
+            startup_timeout,
+            min_peers,
+            grace_period,
             validators,
-            collators,
+            parachains,
+            context,
+            allocated_ports,
         })
     }
 
     // Launches nodes and awaits termination
     pub fn run(&mut self) -> Result<()> {
-        let tasks = self.generate_tasks()?;
-        let mut task_manager = TaskManager::new(tasks);
+        self.ensure_ports_available()?;
+        print!("{}", self.port_map());
+
+        let workspace = &self.context.workspace;
+        workspace.prepare(self.node_names())?;
+        println!("Chain data in {}", workspace.dir.to_string()?);
+
+        let result = self
+            .insert_session_keys()
+            .and_then(|()| self.task_manager()?.run());
+        self.context.workspace.clean_up(self.node_names())?;
+
+        result
+    }
+
+    /// Launches nodes in the background, returning a handle which shuts them down once dropped
+    ///
+    /// Unlike `run`, no signal handler is installed
+    pub fn start(&mut self) -> Result<Network> {
+        self.ensure_ports_available()?;
+        self.context.workspace.prepare(self.node_names())?;
+        if let Err(err) = self.insert_session_keys() {
+            self.context.workspace.clean_up(self.node_names())?;
+            return Err(err);
+        }
+
+        let nodes = self.node_names().map(str::to_owned).collect();
+        let workspace = self.context.workspace.clone();
+
+        Network::spawn(
+            self.task_manager()?,
+            self.port_map(),
+            self.allocated_ports.to_owned(),
+            workspace,
+            nodes,
+        )
+    }
+
+    /// Inserts the session keys of the validators into their keystores, which `prepare` may just
+    /// have purged
+    fn insert_session_keys(&self) -> Result<()> {
+        self.validators
+            .iter()
+            .try_for_each(|validator| validator.insert_session_keys(&self.context))
+    }
+
+    fn task_manager(&mut self) -> Result<TaskManager> {
+        let phases = self.generate_phases()?;
+
+        TaskManager::new(
+            phases,
+            self.startup_timeout,
+            self.min_peers,
+            self.grace_period,
+            self.context.output.target()?,
+        )
+    }
+
+    /// Names of every node
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        let validator_names = self.validators.iter().map(Node::name);
+        let collator_names = self.collators().map(Node::name);
+
+        validator_names.chain(collator_names)
+    }
 
-        task_manager.run()
+    /// Collators of every parachain
+    pub fn collators(&self) -> impl Iterator<Item = &Collator> {
+        self.parachains
+            .iter()
+            .flat_map(|parachain| parachain.collators())
     }
 
-    pub fn generate_tasks(&mut self) -> Result<Vec<Task>> {
-        let validator_tasks = self.validators.iter().map(|v| v.create_task());
-        let collator_tasks = self.collators.iter().map(|c| c.create_task());
+    /// Groups node tasks into startup phases, relay chain validators before collators
+    pub fn generate_phases(&mut self) -> Result<Vec<Phase>> {
+        let validator_tasks = self.validators.iter().map(|v| v.create_task(&self.context));
+        let collator_tasks = self.collators().map(|c| c.create_task(&self.context));
 
-        validator_tasks.chain(collator_tasks).collect()
+        Ok(vec![
+            Phase::new("validators", validator_tasks.collect::<Result<_>>()?),
+            Phase::new("collators", collator_tasks.collect::<Result<_>>()?),
+        ])
+    }
+
+    /// Ports of every node, validators before collators
+    pub fn port_map(&self) -> PortMap {
+        let validator_ports = self.validators.iter().map(Validator::node_ports);
+        let collator_ports = self.collators().map(Collator::node_ports);
+
+        PortMap {
+            nodes: validator_ports.chain(collator_ports).collect(),
+        }
+    }
+
+    /// Readiness probes for every node
+    pub fn probes(&self) -> Vec<Probe> {
+        let validator_probes = self.validators.iter().map(Probe::from_node);
+        let collator_probes = self.collators().map(Probe::from_node);
+
+        validator_probes.chain(collator_probes).collect()
+    }
+
+    /// Blocks until every node is ready, failing once `timeout` has elapsed
+    pub fn wait_until_ready(&self, timeout: Duration) -> Result<()> {
+        readiness::wait_until_ready(&self.probes(), self.min_peers, timeout)
     }
 
     pub fn ensure_unique_ports(&self) -> Result<()> {
@@ -101,6 +225,61 @@ impl<'a> Launcher {
         self.validators.iter().try_for_each(check_validator)?;
 
         let check_collator = |c| check_node(&mut ports, c);
-        self.collators.iter().try_for_each(check_collator)
+        self.collators().try_for_each(check_collator)
+    }
+
+    /// Checks that no port of any node is already bound on the host, e.g. by a node left over
+    /// from a previous run
+    pub fn ensure_ports_available(&self) -> Result<()> {
+        fn check_node(node: &impl Node) -> Result<()> {
+            match node
+                .ports()
+                .into_iter()
+                .flatten()
+                .find(|p| !port::is_bindable(*p))
+            {
+                Some(p) => Err(Error::PortBound {
+                    node: node.name().to_owned(),
+                    port: p,
+                    pid: port::find_listener(p),
+                }),
+                None => Ok(()),
+            }
+        }
+
+        self.validators.iter().try_for_each(check_node)?;
+        self.collators().try_for_each(check_node)
+    }
+}
+
+// Makes every node bootstrap from the other nodes of its chain, with the embedded relay chain
+// nodes of collators bootstrapping from the validators
+fn connect_nodes(validators: &mut [Validator], parachains: &mut [Parachain]) {
+    let validator_bootnodes: Vec<_> = validators.iter().map(Validator::bootnode).collect();
+    for (index, validator) in validators.iter_mut().enumerate() {
+        validator.set_bootnodes(other_bootnodes(&validator_bootnodes, index));
     }
+
+    let relay_bootnodes: Vec<_> = validator_bootnodes.into_iter().flatten().collect();
+    for parachain in parachains.iter_mut() {
+        let collator_bootnodes: Vec<_> = parachain
+            .collators()
+            .iter()
+            .map(Collator::bootnode)
+            .collect();
+        for (index, collator) in parachain.collators_mut().iter_mut().enumerate() {
+            collator.set_bootnodes(other_bootnodes(&collator_bootnodes, index));
+            collator.set_relay_bootnodes(relay_bootnodes.to_owned());
+        }
+    }
+}
+
+// Known bootnodes of a chain, except that of the node at `index`
+fn other_bootnodes(bootnodes: &[Option<String>], index: usize) -> Vec<String> {
+    bootnodes
+        .iter()
+        .enumerate()
+        .filter(|(other, _)| *other != index)
+        .filter_map(|(_, bootnode)| bootnode.to_owned())
+        .collect()
 }
@@ -3,16 +3,32 @@
 #![allow(clippy::from_over_into)]
 
 mod config;
+mod context;
 mod error;
+mod keystore;
 mod launcher;
+mod logs;
+mod network;
 pub mod node;
 mod path_buffer;
+pub mod port;
+pub mod readiness;
 pub mod sub_command;
-mod task;
+pub mod task;
 pub mod util;
+mod workspace;
 
-pub use config::Config;
+pub use config::{
+    Account, Config, ConfigBuilder, ConfigFormat, ConfigIssue, DevAccount, DevKey, Endowment,
+    GenesisConfig, NodeBuilder, ParachainBuilder, PatchOperation, RelayChainBuilder, SpecPatch,
+    ValidatorSeed,
+};
+pub use context::{Context, NodeOutput};
 pub use error::{Error, Result};
+pub use keystore::{KeysFile, KeystoreKey, ValidatorKeystore};
 pub use launcher::Launcher;
+pub use network::Network;
 pub use path_buffer::PathBuffer;
-pub(crate) use task::Task;
+pub use task::{LogFilter, LogLevel, RestartPolicy, StderrLog};
+pub use task::{Task, TaskManager};
+pub use workspace::Workspace;
@@ -0,0 +1,264 @@
+use crate::{PathBuffer, Result};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    os::unix,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Number of rotated files kept per node log, as `<name>.log.1` to `<name>.log.<n>`
+const ROTATED_LOGS: usize = 5;
+
+/// Name of the symlink pointing at the most recent run directory
+const LATEST: &str = "latest";
+
+/// Creates a run directory named after the current UTC time under `log_dir`, pointing the
+/// `latest` symlink at it and removing all but the newest `keep_runs` runs
+pub fn create_run_dir(log_dir: &PathBuffer, keep_runs: usize) -> Result<PathBuffer> {
+    fs::create_dir_all(log_dir.as_ref())?;
+
+    let timestamp = timestamp(SystemTime::now());
+    let mut name = timestamp.clone();
+    let mut attempt = 1;
+    while log_dir.join(&name).as_ref().exists() {
+        attempt += 1;
+        name = format!("{}-{}", timestamp, attempt);
+    }
+
+    let run_dir = log_dir.join(&name);
+    fs::create_dir(run_dir.as_ref())?;
+
+    let latest = log_dir.join(LATEST);
+    if fs::symlink_metadata(latest.as_ref()).is_ok() {
+        fs::remove_file(latest.as_ref())?;
+    }
+    unix::fs::symlink(&name, latest.as_ref())?;
+
+    prune_runs(log_dir, keep_runs.max(1))?;
+
+    Ok(run_dir)
+}
+
+// Removes the oldest run directories until at most `keep_runs` are left, leaving anything not
+// created by `create_run_dir` untouched
+fn prune_runs(log_dir: &PathBuffer, keep_runs: usize) -> Result<()> {
+    let mut runs: Vec<String> = fs::read_dir(log_dir.as_ref())?
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_run_name(name))
+        .collect();
+
+    // Timestamps sort chronologically
+    runs.sort();
+    let excess = runs.len().saturating_sub(keep_runs);
+    for run in runs.iter().take(excess) {
+        fs::remove_dir_all(log_dir.join(run).as_ref())?;
+    }
+
+    Ok(())
+}
+
+// Formats `time` as `YYYY-MM-DD_HH-MM-SS` in UTC
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+
+    // Converts days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+// Whether `name` is a run directory name, i.e. a timestamp with an optional `-<n>` suffix
+fn is_run_name(name: &str) -> bool {
+    const PATTERN: &[u8] = b"0000-00-00_00-00-00";
+
+    let (timestamp, suffix) = name.split_at(name.len().min(PATTERN.len()));
+    let timestamp_matches = timestamp.len() == PATTERN.len()
+        && timestamp.bytes().zip(PATTERN.iter()).all(|(c, p)| match p {
+            b'0' => c.is_ascii_digit(),
+            _ => c == *p,
+        });
+    let suffix_matches = match suffix.strip_prefix('-') {
+        Some(n) => !n.is_empty() && n.bytes().all(|c| c.is_ascii_digit()),
+        None => suffix.is_empty(),
+    };
+
+    timestamp_matches && suffix_matches
+}
+
+/// A log file which is rotated to `<path>.1` once it grows past `max_size` bytes
+#[derive(Debug)]
+pub struct RotatingLog {
+    path: PathBuffer,
+    file: File,
+    size: u64,
+    max_size: u64,
+}
+
+impl RotatingLog {
+    /// Opens the log at `path`, appending to it if it exists
+    pub fn open(path: PathBuffer, max_size: u64) -> Result<Self> {
+        let file = open_append(path.as_ref())?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &[u8]) -> Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    // Shifts `<path>.<n>` to `<path>.<n + 1>`, dropping the oldest, and starts a new file
+    fn rotate(&mut self) -> Result<()> {
+        let path = self.path.to_string()?;
+        let rotated = |n: usize| PathBuffer::from(format!("{}.{}", path, n).as_str());
+
+        for n in (1..ROTATED_LOGS).rev() {
+            match fs::rename(rotated(n).as_ref(), rotated(n + 1).as_ref()) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            }
+        }
+        fs::rename(self.path.as_ref(), rotated(1).as_ref())?;
+
+        self.file = open_append(self.path.as_ref())?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    File::options().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process, time::Duration};
+
+    // Empty directory for the test `name`
+    fn test_dir(name: &str) -> PathBuffer {
+        let dir = env::temp_dir().join(format!("pendulum-launch-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.into()
+    }
+
+    fn entries(dir: &PathBuffer) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir.as_ref())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn timestamps_are_utc_civil_times() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01_00-00-00");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(timestamp(leap_day), "2024-02-29_12-34-56");
+    }
+
+    #[test]
+    fn run_names_are_timestamps_with_an_optional_suffix() {
+        assert!(is_run_name("2024-02-29_12-34-56"));
+        assert!(is_run_name("2024-02-29_12-34-56-2"));
+        assert!(!is_run_name("2024-02-29_12-34-56-"));
+        assert!(!is_run_name("2024-02-29_12-34-56-x"));
+        assert!(!is_run_name("2024-02-29 12-34-56"));
+        assert!(!is_run_name("2024-02-29"));
+        assert!(!is_run_name(LATEST));
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_runs_only() {
+        let dir = test_dir("prune-runs");
+        for name in [
+            "2024-01-01_00-00-00",
+            "2024-01-01_00-00-00-2",
+            "2024-01-02_00-00-00",
+            "notes",
+        ] {
+            fs::create_dir(dir.join(name).as_ref()).unwrap();
+        }
+
+        prune_runs(&dir, 2).unwrap();
+        assert_eq!(
+            entries(&dir),
+            ["2024-01-01_00-00-00-2", "2024-01-02_00-00-00", "notes"]
+        );
+        fs::remove_dir_all(dir.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn latest_points_at_the_newest_run() {
+        let dir = test_dir("create-run-dir");
+        let first = create_run_dir(&dir, 5).unwrap();
+        let second = create_run_dir(&dir, 5).unwrap();
+
+        assert_ne!(first.as_ref(), second.as_ref());
+        assert_eq!(
+            fs::canonicalize(dir.join(LATEST).as_ref()).unwrap(),
+            fs::canonicalize(second.as_ref()).unwrap()
+        );
+        fs::remove_dir_all(dir.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn logs_rotate_past_their_max_size() {
+        let dir = test_dir("rotating-log");
+        let mut log = RotatingLog::open(dir.join("node.log"), 8).unwrap();
+        for line in 0..(ROTATED_LOGS + 2) {
+            log.write_line(format!("line {}\n", line).as_bytes())
+                .unwrap();
+        }
+
+        let read = |name: &str| fs::read_to_string(dir.join(name).as_ref()).unwrap();
+        assert_eq!(read("node.log"), format!("line {}\n", ROTATED_LOGS + 1));
+        assert_eq!(read("node.log.1"), format!("line {}\n", ROTATED_LOGS));
+        assert_eq!(
+            read(&format!("node.log.{}", ROTATED_LOGS)),
+            "line 1\n",
+            "the oldest line is dropped"
+        );
+        assert_eq!(entries(&dir).len(), ROTATED_LOGS + 1);
+        fs::remove_dir_all(dir.as_ref()).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    port::{self, NodePorts, PortMap},
+    task::{self, FinishedPair, TaskManager},
+    Error, Result, Workspace,
+};
+use std::{
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// Handle to a network started with `Launcher::start`, which is shut down when dropped
+#[derive(Debug)]
+pub struct Network {
+    ports: PortMap,
+    allocated_ports: Vec<u16>,
+    finished_pair: Arc<FinishedPair>,
+    started: Receiver<Result<()>>,
+    ready: bool,
+    supervisor: Option<JoinHandle<Result<()>>>,
+    workspace: Workspace,
+    nodes: Vec<String>,
+}
+
+impl Network {
+    /// Starts the tasks of `task_manager` on a supervisor thread
+    pub(crate) fn spawn(
+        mut task_manager: TaskManager,
+        ports: PortMap,
+        allocated_ports: Vec<u16>,
+        workspace: Workspace,
+        nodes: Vec<String>,
+    ) -> Result<Self> {
+        let finished_pair = Arc::new((Mutex::new(false), Condvar::new()));
+        let (started_sender, started) = mpsc::channel();
+
+        let supervisor_pair = Arc::clone(&finished_pair);
+        let supervisor = thread::Builder::new()
+            .name("network-supervisor".to_owned())
+            .spawn(move || {
+                let result = task_manager.start(&supervisor_pair);
+                let failed = result.is_err();
+                // The receiving network may already be dropped
+                let _ = started_sender.send(result);
+
+                match failed {
+                    true => Ok(()),
+                    false => task_manager.supervise_until(&supervisor_pair),
+                }
+            })?;
+
+        Ok(Self {
+            ports,
+            allocated_ports,
+            finished_pair,
+            started,
+            ready: false,
+            supervisor: Some(supervisor),
+            workspace,
+            nodes,
+        })
+    }
+
+    /// Ports of every node
+    #[inline]
+    pub fn ports(&self) -> &PortMap {
+        &self.ports
+    }
+
+    /// Ports of the node named `name`
+    pub fn node(&self, name: &str) -> Option<&NodePorts> {
+        self.ports.nodes.iter().find(|node| node.name == name)
+    }
+
+    /// Blocks until every node is started and the network is healthy, failing with the startup
+    /// error of the network or once `timeout` has elapsed
+    pub fn wait_ready(&mut self, timeout: Duration) -> Result<()> {
+        if self.ready {
+            return Ok(());
+        }
+
+        match self.started.recv_timeout(timeout) {
+            Ok(result) => {
+                result?;
+                self.ready = true;
+                Ok(())
+            }
+            Err(RecvTimeoutError::Timeout) => Err(Error::StartupTimeout(format!(
+                "network not ready after {}s",
+                timeout.as_secs()
+            ))),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::ProcessFailed(
+                "network stopped before becoming ready".to_owned(),
+            )),
+        }
+    }
+
+    /// Shuts every node down, deleting their chain data if the workspace is cleaned up, releases
+    /// the ports allocated to them and returns any error raised while supervising them
+    pub fn stop(mut self) -> Result<()> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        let supervisor = match self.supervisor.take() {
+            Some(supervisor) => supervisor,
+            None => return Ok(()),
+        };
+
+        task::finish(&self.finished_pair)?;
+        let result = supervisor
+            .join()
+            .map_err(|_| Error::ProcessFailed("network supervisor panicked".to_owned()))?;
+        port::release(&self.allocated_ports)?;
+        self.workspace
+            .clean_up(self.nodes.iter().map(String::as_str))?;
+
+        result
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        if let Err(err) = self.shutdown() {
+            eprintln!("Failed to shut down network: {}", err);
+        }
+    }
+}
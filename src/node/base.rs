@@ -1,12 +1,17 @@
-use crate::{error::Result, launcher::LOG_DIR, util, PathBuffer};
+use crate::{
+    error::Result, port::NodePorts, task::RestartPolicy, util, workspace, Context, PathBuffer,
+    Workspace,
+};
 use serde::{Deserialize, Serialize};
-use std::{
-    fs::File,
-    process::{self, Stdio},
-    sync::Arc,
+use std::process;
+
+use super::{
+    key::{self, NodeKey},
+    AsCommand, Node,
 };
 
-use super::{AsCommand, Node};
+/// Args with which a node sets its own bootnodes
+const BOOTNODE_ARGS: [&str; 1] = ["--bootnodes"];
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BaseNode {
@@ -17,7 +22,14 @@ pub struct BaseNode {
     args: Vec<String>,
     port: u16,
     ws_port: u16,
-    rpc_port: Option<u16>,
+    rpc_port: u16,
+    restart_policy: RestartPolicy,
+    /// Network key, unless the node's args set their own
+    #[serde(skip)]
+    node_key: Option<NodeKey>,
+    /// Addresses of the other nodes of the chain
+    #[serde(skip)]
+    bootnodes: Vec<String>,
 }
 
 impl BaseNode {
@@ -29,8 +41,14 @@ impl BaseNode {
         args: Vec<String>,
         port: u16,
         ws_port: u16,
-        rpc_port: Option<u16>,
+        rpc_port: u16,
+        restart_policy: RestartPolicy,
     ) -> Self {
+        let node_key = match key::sets_node_key(&args) {
+            true => None,
+            false => Some(NodeKey::derive(&name)),
+        };
+
         Self {
             name,
             bin,
@@ -40,14 +58,58 @@ impl BaseNode {
             port,
             ws_port,
             rpc_port,
+            restart_policy,
+            node_key,
+            bootnodes: Vec::new(),
         }
     }
 
+    #[inline]
+    pub fn bin(&self) -> &PathBuffer {
+        &self.bin
+    }
+
     #[inline]
     pub fn chain(&self) -> &PathBuffer {
         &self.chain
     }
 
+    /// Directory the node keeps its chain data in, `None` if it runs with `--tmp`
+    #[inline]
+    pub fn base_path(&self, workspace: &Workspace) -> Option<PathBuffer> {
+        workspace::base_path(workspace, &self.name, &self.args)
+    }
+
+    #[inline]
+    pub fn restart_policy(&self) -> RestartPolicy {
+        self.restart_policy
+    }
+
+    #[inline]
+    pub fn node_key(&self) -> Option<&NodeKey> {
+        self.node_key.as_ref()
+    }
+
+    /// Address other nodes of the chain reach the node at, if its peer ID is known
+    pub fn bootnode(&self) -> Option<String> {
+        self.node_key.as_ref().map(|key| key.multiaddr(self.port))
+    }
+
+    /// Connects the node to `bootnodes` on startup, unless its args set their own
+    pub fn set_bootnodes(&mut self, bootnodes: Vec<String>) {
+        self.bootnodes = bootnodes;
+    }
+
+    pub fn node_ports(&self) -> NodePorts {
+        NodePorts {
+            name: self.name.to_owned(),
+            port: self.port,
+            ws_port: self.ws_port,
+            rpc_port: self.rpc_port,
+            relay: None,
+        }
+    }
+
     #[inline]
     pub fn get_log_name(&self) -> Result<String> {
         Ok(format!("{}.log", self.name))
@@ -73,21 +135,30 @@ impl Node for BaseNode {
                 self.port.to_string(),
                 "--ws-port".to_owned(),
                 self.ws_port.to_string(),
+                "--rpc-port".to_owned(),
+                self.rpc_port.to_string(),
             ]
             .as_mut(),
         );
 
-        if let Some(rpc_port) = self.rpc_port {
-            args.push("--rpc-port".to_owned());
-            args.push(rpc_port.to_string());
-        };
+        if let Some(node_key) = &self.node_key {
+            args.push("--node-key".to_owned());
+            args.push(node_key.secret_hex());
+        }
+
+        args.append(&mut bootnode_args(&self.args, &self.bootnodes));
 
         Ok(args)
     }
 
     #[inline]
     fn ports(&self) -> Vec<Option<u16>> {
-        vec![self.port.into(), self.ws_port.into(), self.rpc_port]
+        vec![self.port.into(), self.ws_port.into(), self.rpc_port.into()]
+    }
+
+    #[inline]
+    fn rpc_port(&self) -> u16 {
+        self.rpc_port
     }
 
     #[inline]
@@ -104,18 +175,15 @@ impl Node for BaseNode {
 }
 
 impl AsCommand for BaseNode {
-    fn as_command_internal(&self) -> Result<process::Command> {
-        let log = match &*Arc::clone(&LOG_DIR).read()? {
-            Some(path) => {
-                let path = path.join(self.get_log_name()?);
-                let file = File::create(path.as_ref())?;
-                Stdio::from(file)
-            }
-            None => Stdio::null(),
-        };
-
-        let mut command = process::Command::new(self.bin.as_ref());
-        command.stdout(log).args(self.args()?);
+    fn as_command_internal(&self, context: &Context) -> Result<process::Command> {
+        let mut command = context.command(&self.bin);
+        command.args(self.args()?);
+
+        // Chain data goes to the workspace unless the node's args say otherwise
+        if !workspace::sets_base_path(&self.args) {
+            let base_path = context.workspace.base_path(&self.name);
+            command.arg("--base-path").arg(base_path.as_ref());
+        }
 
         Ok(command)
     }
@@ -133,3 +201,15 @@ impl AsCommand for BaseNode {
         Ok(command.join(" "))
     }
 }
+
+/// `--bootnodes` followed by `bootnodes`, or nothing if there are none or `args` set their own
+pub(super) fn bootnode_args(args: &[String], bootnodes: &[String]) -> Vec<String> {
+    if bootnodes.is_empty() || util::has_arg(args, &BOOTNODE_ARGS) {
+        return Vec::new();
+    }
+
+    let mut bootnode_args = vec!["--bootnodes".to_owned()];
+    bootnode_args.extend_from_slice(bootnodes);
+
+    bootnode_args
+}
@@ -1,5 +1,13 @@
-use super::{base::BaseNode, AsCommand, Node};
-use crate::{config::CollatorConfig, error::Result, util, PathBuffer, Task};
+use super::{
+    base::{self, BaseNode},
+    AsCommand, Node,
+};
+use crate::{
+    error::Result,
+    port::{NodePorts, RelayPorts},
+    readiness::Probe,
+    util, Context, PathBuffer, Task,
+};
 use serde::{Deserialize, Serialize};
 use std::process;
 
@@ -9,7 +17,10 @@ pub struct CollatorRelay {
     args: Option<Vec<String>>,
     port: u16,
     ws_port: u16,
-    rpc_port: Option<u16>,
+    rpc_port: u16,
+    /// Addresses of the relay chain's validators
+    #[serde(skip)]
+    bootnodes: Vec<String>,
 }
 
 impl CollatorRelay {
@@ -18,7 +29,7 @@ impl CollatorRelay {
         args: Option<Vec<String>>,
         port: u16,
         ws_port: u16,
-        rpc_port: Option<u16>,
+        rpc_port: u16,
     ) -> Self {
         Self {
             chain,
@@ -26,6 +37,7 @@ impl CollatorRelay {
             port,
             ws_port,
             rpc_port,
+            bootnodes: Vec::new(),
         }
     }
 }
@@ -42,17 +54,46 @@ impl Collator {
         Self { inner, relay }
     }
 
-    pub fn create_task(&self) -> Result<Task> {
-        let mut command = self.inner.as_command_internal()?;
+    pub fn create_task(&self, context: &Context) -> Result<Task> {
+        let mut command = self.inner.as_command_internal(context)?;
         command.args(self.args()?);
 
-        Ok(Task::new(command))
+        Ok(Task::new(
+            self.name().to_owned(),
+            command,
+            self.inner.restart_policy(),
+            Some(Probe::from_node(self)),
+        ))
     }
-}
 
-impl From<CollatorConfig> for Collator {
-    fn from(collator_config: CollatorConfig) -> Self {
-        collator_config.into()
+    #[inline]
+    pub fn bootnode(&self) -> Option<String> {
+        self.inner.bootnode()
+    }
+
+    /// Connects the collator to the other collators of its parachain
+    #[inline]
+    pub fn set_bootnodes(&mut self, bootnodes: Vec<String>) {
+        self.inner.set_bootnodes(bootnodes);
+    }
+
+    /// Connects the embedded relay chain node to the relay chain's validators
+    #[inline]
+    pub fn set_relay_bootnodes(&mut self, bootnodes: Vec<String>) {
+        self.relay.bootnodes = bootnodes;
+    }
+
+    pub fn node_ports(&self) -> NodePorts {
+        let relay = RelayPorts {
+            port: self.relay.port,
+            ws_port: self.relay.ws_port,
+            rpc_port: self.relay.rpc_port,
+        };
+
+        NodePorts {
+            relay: Some(relay),
+            ..self.inner.node_ports()
+        }
     }
 }
 
@@ -72,6 +113,8 @@ impl Node for Collator {
             self.relay.port.to_string(),
             "--ws-port".to_owned(),
             self.relay.ws_port.to_string(),
+            "--rpc-port".to_owned(),
+            self.relay.rpc_port.to_string(),
         ];
 
         // Append validator args if there are any, replacing them with None
@@ -81,10 +124,8 @@ impl Node for Collator {
             args.append(&mut validator_args);
         };
 
-        if let Some(rpc_port) = self.relay.rpc_port {
-            args.push("--rpc-port".to_owned());
-            args.push(rpc_port.to_string());
-        };
+        let relay_args = self.relay.args.as_deref().unwrap_or_default();
+        args.append(&mut base::bootnode_args(relay_args, &self.relay.bootnodes));
 
         Ok(args)
     }
@@ -94,12 +135,17 @@ impl Node for Collator {
         ports.append(&mut vec![
             self.relay.port.into(),
             self.relay.ws_port.into(),
-            self.relay.rpc_port,
+            self.relay.rpc_port.into(),
         ]);
 
         ports
     }
 
+    #[inline]
+    fn rpc_port(&self) -> u16 {
+        self.inner.rpc_port()
+    }
+
     #[inline]
     fn specs(&self) -> Result<Vec<String>> {
         Ok(vec![
@@ -115,8 +161,8 @@ impl Node for Collator {
 }
 
 impl AsCommand for Collator {
-    fn as_command_internal(&self) -> Result<process::Command> {
-        let mut command = self.inner.as_command_internal()?;
+    fn as_command_internal(&self, context: &Context) -> Result<process::Command> {
+        let mut command = self.inner.as_command_internal(context)?;
         command.args(self.args()?);
 
         Ok(command)
@@ -0,0 +1,58 @@
+use crate::util;
+use ed25519_dalek::{PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+
+/// Prefix of an ed25519 peer ID, the identity multihash of the protobuf encoded public key
+const PEER_ID_PREFIX: [u8; 6] = [0x00, 0x24, 0x08, 0x01, 0x12, 0x20];
+
+/// Args with which a node sets its own network key
+const NODE_KEY_ARGS: [&str; 2] = ["--node-key", "--node-key-file"];
+
+/// Ed25519 network key of a node, derived from its name so peer IDs are stable across runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeKey {
+    secret: [u8; 32],
+    peer_id: String,
+}
+
+impl NodeKey {
+    pub fn derive(name: &str) -> Self {
+        Self::from_secret(Sha256::digest(name.as_bytes()).into())
+    }
+
+    fn from_secret(secret: [u8; 32]) -> Self {
+        // Any 32 bytes are a valid ed25519 secret key
+        let public = PublicKey::from(&SecretKey::from_bytes(&secret).unwrap());
+
+        let mut peer_id = PEER_ID_PREFIX.to_vec();
+        peer_id.extend_from_slice(public.as_bytes());
+
+        Self {
+            secret,
+            peer_id: bs58::encode(peer_id).into_string(),
+        }
+    }
+
+    /// Secret key in hex, as taken by `--node-key`
+    pub fn secret_hex(&self) -> String {
+        self.secret
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[inline]
+    pub fn peer_id(&self) -> &str {
+        &self.peer_id
+    }
+
+    /// Address of a node listening on `port` of the local host
+    pub fn multiaddr(&self, port: u16) -> String {
+        format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", port, self.peer_id)
+    }
+}
+
+/// Whether `args` already set the network key of a node
+pub(crate) fn sets_node_key(args: &[String]) -> bool {
+    util::has_arg(args, &NODE_KEY_ARGS)
+}
@@ -0,0 +1,40 @@
+use super::Collator;
+
+/// A group of collators running the same parachain
+#[derive(Debug)]
+pub struct Parachain {
+    name: Option<String>,
+    para_id: Option<u32>,
+    collators: Vec<Collator>,
+}
+
+impl Parachain {
+    #[inline]
+    pub fn new(name: Option<String>, para_id: Option<u32>, collators: Vec<Collator>) -> Self {
+        Self {
+            name,
+            para_id,
+            collators,
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    pub fn para_id(&self) -> Option<u32> {
+        self.para_id
+    }
+
+    #[inline]
+    pub fn collators(&self) -> &[Collator] {
+        &self.collators
+    }
+
+    #[inline]
+    pub fn collators_mut(&mut self) -> &mut [Collator] {
+        &mut self.collators
+    }
+}
@@ -1,31 +1,69 @@
 use super::{base::BaseNode, AsCommand, Node};
-use crate::{config::ValidatorConfig, error::Result, Task};
+use crate::{
+    error::Result, port::NodePorts, readiness::Probe, Context, Error, Task, ValidatorKeystore,
+};
 use std::process;
 
 #[derive(Debug)]
-pub struct Validator(BaseNode);
+pub struct Validator {
+    inner: BaseNode,
+    /// Session keys inserted into the node's keystore before it's started
+    keystore: Option<ValidatorKeystore>,
+}
 
 impl Validator {
     #[inline]
-    pub fn new(node: BaseNode) -> Self {
-        Self(node)
+    pub fn new(inner: BaseNode, keystore: Option<ValidatorKeystore>) -> Self {
+        Self { inner, keystore }
     }
 
     #[inline]
-    pub fn create_task(&self) -> Result<Task> {
-        Ok(Task::new(self.as_command_internal()?))
+    pub fn create_task(&self, context: &Context) -> Result<Task> {
+        Ok(Task::new(
+            self.name().to_owned(),
+            self.as_command_internal(context)?,
+            self.inner.restart_policy(),
+            Some(Probe::from_node(self)),
+        ))
     }
-}
 
-impl AsRef<BaseNode> for Validator {
-    fn as_ref(&self) -> &BaseNode {
-        &self.0
+    #[inline]
+    pub fn bootnode(&self) -> Option<String> {
+        self.inner.bootnode()
+    }
+
+    /// Connects the validator to the other validators of the relay chain
+    #[inline]
+    pub fn set_bootnodes(&mut self, bootnodes: Vec<String>) {
+        self.inner.set_bootnodes(bootnodes);
+    }
+
+    #[inline]
+    pub fn node_ports(&self) -> NodePorts {
+        self.inner.node_ports()
+    }
+
+    /// Inserts the validator's session keys, if it has any, into its keystore in the context's
+    /// workspace
+    pub fn insert_session_keys(&self, context: &Context) -> Result<()> {
+        let keystore = match &self.keystore {
+            Some(keystore) => keystore,
+            None => return Ok(()),
+        };
+        let base_path = self.inner.base_path(&context.workspace).ok_or_else(|| {
+            Error::InvalidConfig(format!(
+                "validator {} runs with --tmp, so its session keys can't be inserted",
+                self.name()
+            ))
+        })?;
+
+        keystore.insert(context, self.inner.bin(), self.inner.chain(), &base_path)
     }
 }
 
-impl From<ValidatorConfig> for Validator {
-    fn from(validator_config: ValidatorConfig) -> Self {
-        validator_config.into()
+impl AsRef<BaseNode> for Validator {
+    fn as_ref(&self) -> &BaseNode {
+        &self.inner
     }
 }
 
@@ -42,19 +80,24 @@ impl Node for Validator {
         self.as_ref().ports()
     }
 
+    #[inline]
+    fn rpc_port(&self) -> u16 {
+        self.inner.rpc_port()
+    }
+
     #[inline]
     fn specs(&self) -> Result<Vec<String>> {
-        Ok(vec![self.0.chain().to_string()?])
+        Ok(vec![self.inner.chain().to_string()?])
     }
 
     fn docker_file(&self) -> Result<String> {
-        self.0.docker_file()
+        self.inner.docker_file()
     }
 }
 
 impl AsCommand for Validator {
-    fn as_command_internal(&self) -> Result<process::Command> {
-        let mut command = self.as_ref().as_command_internal()?;
+    fn as_command_internal(&self, context: &Context) -> Result<process::Command> {
+        let mut command = self.as_ref().as_command_internal(context)?;
         command.args(self.args()?);
 
         Ok(command)
@@ -6,7 +6,7 @@ use serde::{
 use std::{
     ffi::OsStr,
     fmt,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
 /// A wrapper around PathBuf for extended impl
@@ -36,6 +36,48 @@ impl PathBuffer {
     pub fn maybe_from(value: Option<&str>) -> Option<Self> {
         value.map(Self::from)
     }
+
+    /// Expands `~` and environment variables, joining the path onto `base` if it's relative
+    pub fn resolve<P: AsRef<Path>>(&self, base: P) -> Result<Self> {
+        self.expand()?.join_onto(base)
+    }
+
+    /// Like `resolve`, but leaves names without a path separator, e.g. `polkadot`, to be looked
+    /// up in `PATH`
+    pub fn resolve_command<P: AsRef<Path>>(&self, base: P) -> Result<Self> {
+        let expanded = self.expand()?;
+        match expanded.is_bare() {
+            true => Ok(expanded),
+            false => expanded.join_onto(base),
+        }
+    }
+
+    /// Whether the path is a bare name without a path separator
+    pub fn is_bare(&self) -> bool {
+        self.0.components().count() == 1
+            && matches!(self.0.components().next(), Some(Component::Normal(_)))
+    }
+
+    fn expand(&self) -> Result<Self> {
+        let path = self.to_string()?;
+        let expanded = shellexpand::full(&path)
+            .map_err(|err| Error::InvalidConfig(format!("{}: {}", path, err)))?;
+
+        Ok(Self::from(expanded.as_ref()))
+    }
+
+    fn join_onto<P: AsRef<Path>>(&self, base: P) -> Result<Self> {
+        let path = self.0.as_path();
+        match path.is_absolute() {
+            true => Ok(Self::from(path.to_path_buf())),
+            false => {
+                let components = path.components().filter(|c| *c != Component::CurDir);
+                Ok(Self::from(
+                    base.as_ref().join(components.collect::<PathBuf>()),
+                ))
+            }
+        }
+    }
 }
 
 impl From<&str> for PathBuffer {
@@ -90,3 +132,53 @@ impl Serialize for PathBuffer {
         serializer.serialize_str(self.to_str().unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn resolve_command(path: &str) -> PathBuf {
+        PathBuffer::from(path)
+            .resolve_command("/config")
+            .unwrap()
+            .as_ref()
+            .to_owned()
+    }
+
+    #[test]
+    fn bare_names_are_left_to_path_lookup() {
+        assert_eq!(resolve_command("polkadot"), Path::new("polkadot"));
+    }
+
+    #[test]
+    fn relative_paths_are_joined_onto_the_base() {
+        assert_eq!(resolve_command("./polkadot"), Path::new("/config/polkadot"));
+        assert_eq!(
+            resolve_command("bin/polkadot"),
+            Path::new("/config/bin/polkadot")
+        );
+        assert_eq!(
+            resolve_command("../polkadot"),
+            Path::new("/config/../polkadot")
+        );
+    }
+
+    #[test]
+    fn absolute_paths_are_untouched() {
+        assert_eq!(
+            resolve_command("/usr/bin/polkadot"),
+            Path::new("/usr/bin/polkadot")
+        );
+    }
+
+    #[test]
+    fn home_and_variables_are_expanded_first() {
+        let home = PathBuf::from(env::var("HOME").unwrap());
+        assert_eq!(resolve_command("~/polkadot"), home.join("polkadot"));
+        assert_eq!(resolve_command("$HOME/polkadot"), home.join("polkadot"));
+        assert!(PathBuffer::from("$PENDULUM_LAUNCH_UNSET/polkadot")
+            .resolve_command("/config")
+            .is_err());
+    }
+}
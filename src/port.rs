@@ -0,0 +1,321 @@
+use crate::{error::SerdeError, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt, fs,
+    net::{Ipv4Addr, TcpListener},
+    path::Path,
+    sync::Mutex,
+};
+
+/// Default first port handed out to nodes without explicit ports
+pub const DEFAULT_PORT_BASE: u16 = 30333;
+
+/// Ports allocated by every allocator of the process and not released yet, so networks launched
+/// side by side don't hand out the same ports before their nodes bind them
+static ALLOCATED: Mutex<BTreeSet<u16>> = Mutex::new(BTreeSet::new());
+
+/// Hands out ports that are neither reserved by the config, allocated earlier in the process
+/// nor in use on the host
+#[derive(Debug)]
+pub struct PortAllocator {
+    base: u16,
+    next: Option<u16>,
+    reserved: HashSet<u16>,
+    allocated: Vec<u16>,
+}
+
+impl PortAllocator {
+    pub fn new(base: u16, reserved: HashSet<u16>) -> Self {
+        Self {
+            base,
+            next: Some(base),
+            reserved,
+            allocated: Vec::new(),
+        }
+    }
+
+    /// Allocates the next free port at or above the base, which stays reserved until it is
+    /// released
+    pub fn allocate(&mut self) -> Result<u16> {
+        let mut allocated = ALLOCATED.lock()?;
+        while let Some(port) = self.next {
+            self.next = port.checked_add(1);
+            if !self.reserved.contains(&port) && !allocated.contains(&port) && is_bindable(port) {
+                self.reserved.insert(port);
+                allocated.insert(port);
+                self.allocated.push(port);
+                return Ok(port);
+            }
+        }
+
+        Err(Error::NoFreePort(self.base))
+    }
+
+    /// Allocates a port for `port` if it isn't set
+    pub fn fill(&mut self, port: &mut Option<u16>) -> Result<()> {
+        if port.is_none() {
+            *port = Some(self.allocate()?);
+        }
+
+        Ok(())
+    }
+
+    /// Ports allocated so far
+    pub fn into_allocated(self) -> Vec<u16> {
+        self.allocated
+    }
+}
+
+/// Releases `ports` allocated earlier, allowing any allocator of the process to hand them out
+/// again
+pub fn release(ports: &[u16]) -> Result<()> {
+    let mut allocated = ALLOCATED.lock()?;
+    for port in ports {
+        allocated.remove(port);
+    }
+
+    Ok(())
+}
+
+/// Whether a listener can currently be bound to `port` on every interface
+pub fn is_bindable(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok()
+}
+
+/// Finds the process listening on `port` through `/proc/net`, if it is visible to us
+#[cfg(target_os = "linux")]
+pub fn find_listener(port: u16) -> Option<u32> {
+    let inodes: Vec<String> = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|table| fs::read_to_string(table).ok())
+        .flat_map(|table| listening_inodes(&table, port))
+        .collect();
+
+    fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .find(|pid| owns_socket(*pid, &inodes))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn find_listener(_port: u16) -> Option<u32> {
+    None
+}
+
+// Socket inodes listening on `port` in a `/proc/net/tcp` table
+#[cfg(target_os = "linux")]
+fn listening_inodes(table: &str, port: u16) -> Vec<String> {
+    // Columns are `sl local_address rem_address st ... inode`, with the address as `IP:PORT` in
+    // hex and `0A` being the LISTEN state
+    const LISTEN: &str = "0A";
+
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let local_port = columns.get(1)?.rsplit(':').next()?;
+            let listening =
+                u16::from_str_radix(local_port, 16).ok()? == port && *columns.get(3)? == LISTEN;
+
+            match listening {
+                true => columns.get(9).map(|inode| inode.to_string()),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+// Whether one of the file descriptors of `pid` is one of the sockets in `inodes`
+#[cfg(target_os = "linux")]
+fn owns_socket(pid: u32, inodes: &[String]) -> bool {
+    let fds = match fs::read_dir(format!("/proc/{}/fd", pid)) {
+        Ok(fds) => fds,
+        Err(_) => return false,
+    };
+
+    fds.flatten()
+        .filter_map(|fd| fs::read_link(fd.path()).ok())
+        .filter_map(|target| target.to_str().map(str::to_owned))
+        .any(|target| {
+            inodes
+                .iter()
+                .any(|inode| target == format!("socket:[{}]", inode))
+        })
+}
+
+/// Ports of a collator's embedded relay chain node
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RelayPorts {
+    pub port: u16,
+    pub ws_port: u16,
+    pub rpc_port: u16,
+}
+
+/// Ports a node listens on
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NodePorts {
+    pub name: String,
+    pub port: u16,
+    pub ws_port: u16,
+    pub rpc_port: u16,
+    pub relay: Option<RelayPorts>,
+}
+
+impl NodePorts {
+    /// WebSocket JSON-RPC endpoint of the node
+    pub fn ws_url(&self) -> String {
+        format!("ws://127.0.0.1:{}", self.ws_port)
+    }
+
+    /// HTTP JSON-RPC endpoint of the node
+    pub fn rpc_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.rpc_port)
+    }
+}
+
+/// Ports of every node in a network, written out so tooling can discover endpoints
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PortMap {
+    pub nodes: Vec<NodePorts>,
+}
+
+impl PortMap {
+    /// Writes the port map to `path` as JSON
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|err| Error::Serde(SerdeError::Serialize(err.to_string())))?;
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for PortMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Ports:")?;
+        for node in self.nodes.iter() {
+            write!(
+                f,
+                "  {}: p2p {}, ws {}, rpc {}",
+                node.name, node.port, node.ws_port, node.rpc_port
+            )?;
+            if let Some(relay) = &node.relay {
+                write!(
+                    f,
+                    ", relay p2p {}, ws {}, rpc {}",
+                    relay.port, relay.ws_port, relay.rpc_port
+                )?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn allocation_skips_reserved_and_bound_ports() {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        let bound = listener.local_addr().unwrap().port();
+        let mut allocator = PortAllocator::new(bound, [bound.wrapping_add(1)].into());
+
+        let first = allocator.allocate().unwrap();
+        let second = allocator.allocate().unwrap();
+        assert!(first > bound.wrapping_add(1));
+        assert!(second > first);
+    }
+
+    #[test]
+    fn fill_keeps_set_ports() {
+        let mut allocator = PortAllocator::new(DEFAULT_PORT_BASE, HashSet::new());
+        let mut port = Some(1);
+        allocator.fill(&mut port).unwrap();
+        assert_eq!(port, Some(1));
+    }
+
+    #[test]
+    fn released_ports_are_allocated_again() {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        let base = listener.local_addr().unwrap().port().wrapping_add(1);
+        drop(listener);
+
+        let mut allocator = PortAllocator::new(base, HashSet::new());
+        let port = allocator.allocate().unwrap();
+        assert_eq!(allocator.into_allocated(), [port]);
+        assert_ne!(
+            PortAllocator::new(port, HashSet::new()).allocate().unwrap(),
+            port
+        );
+
+        release(&[port]).unwrap();
+        assert_eq!(
+            PortAllocator::new(port, HashSet::new()).allocate().unwrap(),
+            port
+        );
+    }
+
+    #[test]
+    fn allocation_fails_past_the_last_port() {
+        let mut allocator = PortAllocator::new(u16::MAX, [u16::MAX].into());
+        assert!(matches!(
+            allocator.allocate(),
+            Err(Error::NoFreePort(u16::MAX))
+        ));
+    }
+
+    fn port_map() -> PortMap {
+        PortMap {
+            nodes: vec![
+                NodePorts {
+                    name: "alice".to_owned(),
+                    port: 30333,
+                    ws_port: 9944,
+                    rpc_port: 9933,
+                    relay: None,
+                },
+                NodePorts {
+                    name: "collator".to_owned(),
+                    port: 30335,
+                    ws_port: 9946,
+                    rpc_port: 9935,
+                    relay: Some(RelayPorts {
+                        port: 30336,
+                        ws_port: 9947,
+                        rpc_port: 9936,
+                    }),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn port_map_is_written_as_json() {
+        let path = env::temp_dir().join(format!("pendulum-launch-ports-{}.json", process::id()));
+        port_map().write(&path).unwrap();
+
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(
+            serde_json::from_str::<PortMap>(&written).unwrap(),
+            port_map()
+        );
+    }
+
+    #[test]
+    fn port_map_lists_every_node() {
+        assert_eq!(
+            port_map().to_string(),
+            "Ports:\n  \
+             alice: p2p 30333, ws 9944, rpc 9933\n  \
+             collator: p2p 30335, ws 9946, rpc 9935, relay p2p 30336, ws 9947, rpc 9936\n"
+        );
+    }
+}
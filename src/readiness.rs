@@ -0,0 +1,247 @@
+use crate::{node::Node, Error, Result};
+use json::JsonValue;
+use std::{
+    io::{Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Time to wait for a port to accept a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Time to wait for a JSON-RPC response
+const RPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Interval at which probes are retried while waiting for readiness
+const PROBE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Result of a `system_health` JSON-RPC call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Health {
+    pub peers: usize,
+    pub is_syncing: bool,
+    pub should_have_peers: bool,
+}
+
+/// Checks whether a node accepts connections on its ports and reports itself as healthy
+#[derive(Debug, Clone)]
+pub struct Probe {
+    name: String,
+    ports: Vec<u16>,
+    rpc_port: u16,
+}
+
+impl Probe {
+    pub fn new(name: String, ports: Vec<u16>, rpc_port: u16) -> Self {
+        Self {
+            name,
+            ports,
+            rpc_port,
+        }
+    }
+
+    pub fn from_node(node: &impl Node) -> Self {
+        let ports = node.ports().into_iter().flatten().collect();
+        Self::new(node.name().to_owned(), ports, node.rpc_port())
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether every port of the node accepts TCP connections
+    pub fn ports_open(&self) -> bool {
+        self.ports.iter().all(|port| {
+            let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, *port));
+            TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok()
+        })
+    }
+
+    /// Queries the node's `system_health` over JSON-RPC
+    pub fn health(&self) -> Result<Health> {
+        let response = rpc_call(self.rpc_port, "system_health")?;
+        let result = &response["result"];
+
+        let invalid = || Error::InvalidJsonValue(format!("system_health result: {}", result));
+        Ok(Health {
+            peers: result["peers"].as_usize().ok_or_else(invalid)?,
+            is_syncing: result["isSyncing"].as_bool().ok_or_else(invalid)?,
+            should_have_peers: result["shouldHavePeers"].as_bool().unwrap_or(true),
+        })
+    }
+
+    /// Whether the node's ports are open, it isn't syncing and it has at least `min_peers` peers
+    pub fn is_ready(&self, min_peers: usize) -> bool {
+        if !self.ports_open() {
+            return false;
+        }
+
+        match self.health() {
+            Ok(health) => !health.is_syncing && health.peers >= min_peers,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Blocks until every probe is ready, failing once `timeout` has elapsed
+pub fn wait_until_ready(probes: &[Probe], min_peers: usize, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let pending: Vec<&str> = probes
+            .iter()
+            .filter(|probe| !probe.is_ready(min_peers))
+            .map(|probe| probe.name())
+            .collect();
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            return Err(Error::StartupTimeout(format!(
+                "network not ready after {}s, waiting for {}",
+                timeout.as_secs(),
+                pending.join(", ")
+            )));
+        }
+
+        thread::sleep(PROBE_INTERVAL);
+    }
+}
+
+// Performs a parameterless JSON-RPC call over HTTP, returning the parsed response
+fn rpc_call(port: u16, method: &str) -> Result<JsonValue> {
+    let body = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"{}","params":[]}}"#,
+        method
+    );
+    let request = format!(
+        "POST / HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        port,
+        body.len(),
+        body
+    );
+
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(RPC_TIMEOUT))?;
+    stream.set_write_timeout(Some(RPC_TIMEOUT))?;
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    // Drop the status line and headers
+    match response.split_once("\r\n\r\n") {
+        Some((_, body)) => Ok(json::parse(body)?),
+        None => Err(Error::ProcessFailed(format!(
+            "Malformed JSON-RPC response on port {}",
+            port
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    // Starts a JSON-RPC stub answering every request with `result`, returning its port
+    fn stub(result: &'static str) -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !String::from_utf8_lossy(&request).ends_with("}") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buf[..read]),
+                    }
+                }
+
+                let body = format!(r#"{{"jsonrpc":"2.0","id":1,"result":{}}}"#, result);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        port
+    }
+
+    fn probe(port: u16) -> Probe {
+        Probe::new("alice".to_owned(), vec![port], port)
+    }
+
+    fn closed_port() -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn healthy_node_is_ready() {
+        let probe = probe(stub(
+            r#"{"peers":2,"isSyncing":false,"shouldHavePeers":true}"#,
+        ));
+
+        assert_eq!(
+            probe.health().unwrap(),
+            Health {
+                peers: 2,
+                is_syncing: false,
+                should_have_peers: true
+            }
+        );
+        assert!(probe.is_ready(2));
+        assert!(wait_until_ready(&[probe], 2, Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn syncing_node_is_not_ready() {
+        let probe = probe(stub(
+            r#"{"peers":2,"isSyncing":true,"shouldHavePeers":true}"#,
+        ));
+
+        assert!(probe.health().unwrap().is_syncing);
+        assert!(!probe.is_ready(0));
+    }
+
+    #[test]
+    fn node_below_min_peers_is_not_ready() {
+        let probe = probe(stub(
+            r#"{"peers":1,"isSyncing":false,"shouldHavePeers":true}"#,
+        ));
+
+        assert!(probe.is_ready(1));
+        assert!(!probe.is_ready(2));
+        match wait_until_ready(&[probe], 2, Duration::from_millis(100)) {
+            Err(Error::StartupTimeout(msg)) => assert!(msg.ends_with("waiting for alice")),
+            other => panic!("expected a startup timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_result_is_an_error() {
+        let probe = probe(stub(r#"{"peers":"many","isSyncing":false}"#));
+
+        assert!(matches!(probe.health(), Err(Error::InvalidJsonValue(_))));
+        assert!(!probe.is_ready(0));
+    }
+
+    #[test]
+    fn closed_port_is_not_ready() {
+        let probe = probe(closed_port());
+
+        assert!(!probe.ports_open());
+        assert!(probe.health().is_err());
+        assert!(!probe.is_ready(0));
+    }
+}
@@ -1,26 +1,174 @@
-use crate::{error::Result, util};
+use crate::{error::Result, util, Error};
+use sha2::{Digest, Sha256};
 use std::{fs, process};
 
-/// Export genesis data to an `outdir` if provided or to the project root
-pub fn export_genesis(bin: String, chain: String, name: String, outdir: String) -> Result<()> {
-    // Generates genesis data, given a name
-    let generate = |suffix: &str| -> Result<()> {
-        let cmd = format!("export-genesis-{}", suffix);
-        let output = process::Command::new(&bin)
-            .args([&cmd, "--chain", &chain])
-            .output()?;
+/// Subcommand exporting the genesis head, renamed from `export-genesis-state` by newer collators
+const HEAD_COMMANDS: [&str; 2] = ["export-genesis-head", "export-genesis-state"];
+pub(super) const WASM_COMMAND: &str = "export-genesis-wasm";
 
-        util::ensure_success(&output)?;
+/// Export genesis data to an `outdir` if provided or to the project root, along with a
+/// `<name>-manifest.json` holding the para ID and the path of every file and the hash of its
+/// decoded bytes
+pub fn export_genesis(
+    bin: String,
+    chain: String,
+    name: String,
+    outdir: String,
+    para_id: Option<u32>,
+) -> Result<()> {
+    let head_command = head_command(&bin)?;
+    let mut manifest = json::object! {
+        para_id: para_id.or_else(|| spec_para_id(&chain)),
+        chain: chain.as_str(),
+    };
 
-        let data = String::from_utf8(output.stdout)?;
+    // Generate genesis-wasm and genesis-state
+    for (suffix, command) in [("wasm", WASM_COMMAND), ("state", head_command)] {
+        let data = export(&bin, command, &chain)?;
+        let bytes = decode_hex(&data).ok_or_else(|| {
+            Error::ProcessFailed(format!("{} {} exported invalid hex", bin, command))
+        })?;
         let out_file = format!("{}/{}-{}", outdir, name, suffix);
-        fs::write(out_file, data)?;
+        fs::write(&out_file, &data)?;
+
+        manifest[suffix] = json::object! {
+            file: out_file.as_str(),
+            sha256: sha256(&bytes),
+        };
+    }
 
-        Ok(())
+    let out_file = format!("{}/{}-manifest.json", outdir, name);
+    fs::write(out_file, manifest.pretty(2))?;
+
+    Ok(())
+}
+
+/// Subcommand exporting the genesis head which `bin` lists in its `--help`
+pub(super) fn head_command(bin: &str) -> Result<&'static str> {
+    let output = process::Command::new(bin).arg("--help").output()?;
+
+    util::ensure_success(&output)?;
+
+    listed_head_command(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        Error::ProcessFailed(format!(
+            "{} supports neither {} nor {} along with {}",
+            bin, HEAD_COMMANDS[0], HEAD_COMMANDS[1], WASM_COMMAND
+        ))
+    })
+}
+
+// Subcommand exporting the genesis head listed in the `--help` output `help`, if it lists the
+// one exporting the wasm too
+fn listed_head_command(help: &str) -> Option<&'static str> {
+    let lists = |command: &str| {
+        help.lines()
+            .any(|line| line.split_whitespace().next() == Some(command))
     };
 
-    // Generate genesis-wasm and genesis-state
-    ["wasm", "state"]
+    HEAD_COMMANDS
         .into_iter()
-        .try_for_each(|suffix| generate(suffix))
+        .find(|command| lists(command))
+        .filter(|_| lists(WASM_COMMAND))
+}
+
+/// Runs the genesis export `command` of a collator, returning the exported hex data
+pub(super) fn export(bin: &str, command: &str, chain: &str) -> Result<String> {
+    let output = process::Command::new(bin)
+        .args([command, "--chain", chain])
+        .output()?;
+
+    util::ensure_success(&output)?;
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+// Para ID set by the chain spec `chain`, if it's a file
+fn spec_para_id(chain: &str) -> Option<u32> {
+    let spec = json::parse(&fs::read_to_string(chain).ok()?).ok()?;
+    spec["para_id"].as_u32().or_else(|| spec["paraId"].as_u32())
+}
+
+// Bytes of the hex data `data`, with or without a `0x` prefix
+fn decode_hex(data: &str) -> Option<Vec<u8>> {
+    let data = data.trim();
+    let digits = data.strip_prefix("0x").unwrap_or(data);
+    if !digits.len().is_multiple_of(2) || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn sha256(data: &[u8]) -> String {
+    let hash: String = Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("0x{}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELP: &str = "\
+Usage: polkadot-parachain [OPTIONS] [COMMAND]
+
+Commands:
+  build-spec            Build a chain specification
+  check-block           Validate blocks
+  export-blocks         Export blocks
+  export-state          Export the state of a given block into a chain spec
+  import-blocks         Import blocks
+  purge-chain           Remove the whole chain
+  revert                Revert the chain to a previous state
+  export-genesis-head   Export the genesis head data of the parachain
+  export-genesis-wasm   Export the genesis wasm of the parachain
+  key                   Key management cli utilities
+  help                  Print this message or the help of the given subcommand(s)
+
+Options:
+      --export-genesis-state  Not a subcommand
+";
+
+    #[test]
+    fn newer_collators_export_the_head() {
+        assert_eq!(listed_head_command(HELP), Some("export-genesis-head"));
+    }
+
+    #[test]
+    fn older_collators_export_the_state() {
+        let help = HELP.replace("export-genesis-head ", "export-genesis-state");
+        assert_eq!(listed_head_command(&help), Some("export-genesis-state"));
+    }
+
+    #[test]
+    fn options_are_not_commands() {
+        let help = HELP.replace("  export-genesis-head ", "  export-head ");
+        assert_eq!(listed_head_command(&help), None);
+    }
+
+    #[test]
+    fn the_wasm_export_is_required() {
+        let help = HELP.replace("export-genesis-wasm", "export-wasm");
+        assert_eq!(listed_head_command(&help), None);
+    }
+
+    #[test]
+    fn exports_are_hashed_as_bytes() {
+        assert_eq!(decode_hex("0x00ff1a\n"), Some(vec![0x00, 0xff, 0x1a]));
+        assert_eq!(decode_hex("00FF"), Some(vec![0x00, 0xff]));
+        assert_eq!(decode_hex("0x0f0"), None);
+        assert_eq!(decode_hex("0x+f"), None);
+
+        // SHA-256 of the empty input
+        assert_eq!(
+            sha256(&decode_hex("0x").unwrap()),
+            "0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
 }
@@ -48,7 +48,9 @@ services:"#,
         );
 
         self.write_service(&mut docker_compose, &self.launcher.validators)?;
-        self.write_service(&mut docker_compose, &self.launcher.collators)?;
+        for parachain in &self.launcher.parachains {
+            self.write_service(&mut docker_compose, parachain.collators())?;
+        }
 
         Ok(docker_compose)
     }
@@ -103,10 +105,8 @@ services:"#,
             .iter()
             .try_for_each(|v| insert_specs(&mut specs, v))?;
 
-        // let insert_collator_specs = |c| insert_specs(&mut specs, c);
         self.launcher
-            .collators
-            .iter()
+            .collators()
             .try_for_each(|c| insert_specs(&mut specs, c))?;
 
         Ok(Vec::from_iter(specs))
@@ -0,0 +1,164 @@
+use super::{
+    export_genesis::{export, head_command, WASM_COMMAND},
+    generate_specs::write_specs,
+    validator_keys::{self, ValidatorKeys},
+};
+use crate::util;
+use crate::{config::CollatorConfig, Error, KeysFile, Result, ValidatorSeed};
+use serde_json::{json, Value};
+use std::process;
+
+/// Generate relay chain specs from a validator, based on the built-in or plain spec `chain`,
+/// registering `parachains` at genesis and making `validators`, if any, its authorities with
+/// their session keys written to `<name>-keys.json`
+pub fn generate_relay_specs(
+    bin: String,
+    chain: String,
+    name: String,
+    outdir: String,
+    parachains: &[CollatorConfig],
+    validators: &[ValidatorSeed],
+) -> Result<()> {
+    // Generate plain
+    let output = process::Command::new(&bin)
+        .args([
+            "build-spec",
+            "--chain",
+            &chain,
+            "--disable-default-bootnode",
+        ])
+        .output()?;
+
+    util::ensure_success(&output)?;
+
+    let mut data = util::parse_json(&output.stdout)?;
+    let paras = parachains
+        .iter()
+        .enumerate()
+        .map(|(index, parachain)| para_genesis(index, parachain))
+        .collect::<Result<Vec<_>>>()?;
+    register_paras(&mut data, paras)?;
+
+    let validators = match validators.is_empty() {
+        true => Vec::new(),
+        false => {
+            let names = validator_keys::session_key_names(&data)?;
+            validators
+                .iter()
+                .map(|seed| ValidatorKeys::derive(seed, &names))
+                .collect::<Result<Vec<_>>>()?
+        }
+    };
+    if !validators.is_empty() {
+        validator_keys::apply(&mut data, &validators)?;
+    }
+
+    write_specs(&bin, &name, &outdir, &data)?;
+
+    if validators.is_empty() {
+        return Ok(());
+    }
+    let keys = KeysFile {
+        validators: validators.iter().map(ValidatorKeys::keystore).collect(),
+    };
+    keys.write(format!("{}/{}-keys.json", outdir, name))
+}
+
+// Genesis entry of `paras.paras`, holding the genesis head and validation code exported by the
+// parachain's collator
+fn para_genesis(index: usize, parachain: &CollatorConfig) -> Result<(u32, Value)> {
+    let label = match parachain.name() {
+        Some(name) => name.to_owned(),
+        None => format!("parachains[{}]", index),
+    };
+    let para_id = parachain.para_id().ok_or_else(|| {
+        Error::InvalidConfig(format!("parachain {} has no para_id to register", label))
+    })?;
+    let chain = parachain
+        .chain()
+        .ok_or_else(|| Error::InvalidConfig(format!("parachain {} has no chain spec", label)))?
+        .to_string()?;
+    let bin = parachain.bin().to_string()?;
+
+    let genesis = json!({
+        "genesis_head": export(&bin, head_command(&bin)?, &chain)?.trim(),
+        "validation_code": export(&bin, WASM_COMMAND, &chain)?.trim(),
+        "parachain": true,
+    });
+
+    Ok((para_id, genesis))
+}
+
+// Sets the genesis of `paras`, replacing any the spec already registers with the same para ID
+fn register_paras(spec: &mut Value, paras: Vec<(u32, Value)>) -> Result<()> {
+    let pallet = spec
+        .pointer_mut("/genesis/runtime/paras")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| Error::InvalidJsonValue("genesis.runtime.paras".to_owned()))?;
+
+    let registered = pallet.get("paras").and_then(Value::as_array);
+    let mut entries: Vec<_> = registered
+        .into_iter()
+        .flatten()
+        .filter(|entry| {
+            let para_id = entry[0].as_u64();
+            !paras.iter().any(|(id, _)| Some(u64::from(*id)) == para_id)
+        })
+        .cloned()
+        .collect();
+    entries.extend(
+        paras
+            .into_iter()
+            .map(|(para_id, genesis)| json!([para_id, genesis])),
+    );
+    pallet.insert("paras".to_owned(), Value::Array(entries));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genesis(head: &str) -> Value {
+        json!({ "genesis_head": head, "validation_code": "0x00", "parachain": true })
+    }
+
+    #[test]
+    fn paras_replace_those_with_the_same_id() {
+        let registered = json!([[1000, genesis("0x01")], [2000, genesis("0x02")]]);
+        let mut spec = json!({ "genesis": { "runtime": { "paras": { "paras": registered } } } });
+        let paras = vec![(2000, genesis("0x03")), (3000, genesis("0x04"))];
+        register_paras(&mut spec, paras).unwrap();
+
+        assert_eq!(
+            spec["genesis"]["runtime"]["paras"]["paras"],
+            json!([
+                [1000, genesis("0x01")],
+                [2000, genesis("0x03")],
+                [3000, genesis("0x04")]
+            ])
+        );
+    }
+
+    #[test]
+    fn paras_are_registered_in_specs_without_any() {
+        let mut spec = json!({ "genesis": { "runtime": { "paras": {} } } });
+        register_paras(&mut spec, vec![(1000, genesis("0x01"))]).unwrap();
+
+        assert_eq!(
+            spec["genesis"]["runtime"]["paras"]["paras"],
+            json!([[1000, genesis("0x01")]])
+        );
+    }
+
+    #[test]
+    fn specs_need_the_paras_pallet() {
+        let mut spec = json!({ "genesis": { "runtime": { "system": {} } } });
+        let result = register_paras(&mut spec, vec![(1000, genesis("0x01"))]);
+
+        assert!(
+            matches!(result, Err(Error::InvalidJsonValue(path)) if path == "genesis.runtime.paras")
+        );
+    }
+}
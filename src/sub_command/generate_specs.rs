@@ -1,24 +1,49 @@
 use crate::util;
-use crate::{Error, Result};
-use json::JsonValue;
+use crate::{config::CollatorConfig, Error, Result, SpecPatch};
+use serde_json::Value;
 use std::{fs, process};
 
-/// Generate specs from a collator
-pub fn generate_specs(bin: String, name: String, para_id: u32, outdir: String) -> Result<()> {
+/// Generate specs from a collator, based on `chain` or its default chain, applying the genesis and
+/// patches of `parachain` followed by `patches` to the plain spec
+pub fn generate_specs(
+    bin: String,
+    chain: Option<String>,
+    name: String,
+    para_id: u32,
+    outdir: String,
+    parachain: Option<&CollatorConfig>,
+    patches: &[SpecPatch],
+) -> Result<()> {
     // Generate plain
-    let output = process::Command::new(&bin)
-        .args(["build-spec", "--disable-default-bootnode"])
-        .output()?;
+    let mut command = process::Command::new(&bin);
+    command.args(["build-spec", "--disable-default-bootnode"]);
+    if let Some(chain) = &chain {
+        command.args(["--chain", chain]);
+    }
+    let output = command.output()?;
 
     util::ensure_success(&output)?;
 
-    let data = set_para_id(output.stdout, para_id)?;
+    let mut data = set_para_id(output.stdout, para_id)?;
+    if let Some(parachain) = parachain {
+        parachain.apply_genesis(&mut data)?;
+        parachain.apply_patches(&mut data)?;
+    }
+    patches
+        .iter()
+        .try_for_each(|patch| patch.apply(&mut data))?;
+
+    write_specs(&bin, &name, &outdir, &data)
+}
+
+/// Writes the plain spec `data` and the raw spec built from it by `bin` to `outdir`
+pub(super) fn write_specs(bin: &str, name: &str, outdir: &str, data: &Value) -> Result<()> {
     let out_file = format!("{}/{}-plain.json", outdir, name);
 
-    fs::write(&out_file, data.pretty(2))?;
+    fs::write(&out_file, util::pretty_json(data)?)?;
 
     // Generate raw
-    let output = process::Command::new(&bin)
+    let output = process::Command::new(bin)
         .args([
             "build-spec",
             "--chain",
@@ -37,14 +62,13 @@ pub fn generate_specs(bin: String, name: String, para_id: u32, outdir: String) -
     Ok(())
 }
 
-fn set_para_id(data: Vec<u8>, para_id: u32) -> Result<JsonValue> {
-    let data = String::from_utf8(data)?;
-    let mut serialized_data = json::parse(&data)?;
+fn set_para_id(data: Vec<u8>, para_id: u32) -> Result<Value> {
+    let mut serialized_data = util::parse_json(&data)?;
 
     let key = "para_id";
-    match serialized_data[key] {
-        JsonValue::Number(_) => {
-            serialized_data[key] = JsonValue::from(para_id);
+    match serialized_data.get(key) {
+        Some(Value::Number(_)) => {
+            serialized_data[key] = para_id.into();
             Ok(serialized_data)
         }
         _ => Err(Error::InvalidJsonValue(key.to_string())),
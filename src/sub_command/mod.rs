@@ -1,7 +1,10 @@
 mod export_genesis;
 mod generate_docker;
+mod generate_relay_specs;
 mod generate_specs;
+mod validator_keys;
 
 pub use export_genesis::export_genesis;
 pub use generate_docker::GenerateDocker;
+pub use generate_relay_specs::generate_relay_specs;
 pub use generate_specs::generate_specs;
@@ -0,0 +1,329 @@
+use crate::{config::ValidatorSeed, util, Error, KeystoreKey, Result, ValidatorKeystore};
+use serde_json::{json, Map, Value};
+use std::process;
+
+/// Session keys of relay chain runtimes by their name in the genesis config, along with their
+/// key type and scheme
+const SESSION_KEYS: [(&str, &str, &str); 8] = [
+    ("grandpa", "gran", "ed25519"),
+    ("babe", "babe", "sr25519"),
+    ("im_online", "imon", "sr25519"),
+    ("para_validator", "para", "sr25519"),
+    ("para_assignment", "asgn", "sr25519"),
+    ("authority_discovery", "audi", "sr25519"),
+    ("beefy", "beef", "ecdsa"),
+    ("aura", "aura", "sr25519"),
+];
+
+/// Balance of the stash and controller account of every validator
+const ENDOWMENT: u64 = 1 << 60;
+/// Amount bonded by every validator of runtimes with staking
+const STASH: u64 = ENDOWMENT / 1000;
+
+struct SessionKey {
+    name: &'static str,
+    key_type: &'static str,
+    scheme: &'static str,
+    public: String,
+}
+
+/// Accounts and session keys of a validator, derived from its seed
+pub(super) struct ValidatorKeys<'a> {
+    seed: &'a ValidatorSeed,
+    /// `<seed>//stash`, the validator ID
+    stash: String,
+    controller: String,
+    session: Vec<SessionKey>,
+}
+
+impl<'a> ValidatorKeys<'a> {
+    /// Derives the accounts and the session keys `names` of `seed` with the `key inspect`
+    /// command of its binary
+    pub(super) fn derive(seed: &'a ValidatorSeed, names: &[String]) -> Result<Self> {
+        let bin = seed.bin.to_string()?;
+        let session = names
+            .iter()
+            .map(|name| {
+                let (name, key_type, scheme) = SESSION_KEYS
+                    .into_iter()
+                    .find(|(known, _, _)| known == name)
+                    .ok_or_else(|| {
+                        Error::InvalidJsonValue(format!("genesis.runtime.session.keys.{}", name))
+                    })?;
+                Ok(SessionKey {
+                    name,
+                    key_type,
+                    scheme,
+                    public: inspect(&bin, scheme, &seed.seed)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            seed,
+            stash: inspect(&bin, "sr25519", &format!("{}//stash", seed.seed))?,
+            controller: inspect(&bin, "sr25519", &seed.seed)?,
+            session,
+        })
+    }
+
+    /// Session keys to insert into the validator's keystore on launch
+    pub(super) fn keystore(&self) -> ValidatorKeystore {
+        ValidatorKeystore {
+            name: self.seed.name.to_owned(),
+            seed: self.seed.seed.to_owned(),
+            keys: self
+                .session
+                .iter()
+                .map(|key| KeystoreKey {
+                    key_type: key.key_type.to_owned(),
+                    scheme: key.scheme.to_owned(),
+                })
+                .collect(),
+        }
+    }
+
+    fn session_keys(&self) -> Value {
+        self.session
+            .iter()
+            .map(|key| (key.name.to_owned(), Value::from(key.public.as_str())))
+            .collect::<Map<_, _>>()
+            .into()
+    }
+}
+
+// SS58 public key of the `scheme` key derived from the secret URI `suri`
+fn inspect(bin: &str, scheme: &str, suri: &str) -> Result<String> {
+    let output = process::Command::new(bin)
+        .args([
+            "key",
+            "inspect",
+            "--output-type",
+            "json",
+            "--scheme",
+            scheme,
+            suri,
+        ])
+        .output()?;
+
+    util::ensure_success(&output)?;
+
+    let key = json::parse(&String::from_utf8(output.stdout)?)?;
+    key["ss58PublicKey"]
+        .as_str()
+        .or_else(|| key["ss58Address"].as_str())
+        .map(str::to_owned)
+        .ok_or_else(|| Error::InvalidJsonValue("ss58PublicKey".to_owned()))
+}
+
+/// Names of the session keys of the runtime, taken from those the plain spec `spec` sets
+pub(super) fn session_key_names(spec: &Value) -> Result<Vec<String>> {
+    match spec
+        .pointer("/genesis/runtime/session/keys/0/2")
+        .and_then(Value::as_object)
+    {
+        Some(keys) => Ok(keys.keys().cloned().collect()),
+        None => Err(Error::InvalidJsonValue(
+            "genesis.runtime.session.keys".to_owned(),
+        )),
+    }
+}
+
+/// Makes `validators` the authorities of the plain relay chain spec `spec`, endowing their
+/// accounts and, if the runtime has staking, bonding their stash
+pub(super) fn apply(spec: &mut Value, validators: &[ValidatorKeys]) -> Result<()> {
+    let runtime = spec
+        .pointer_mut("/genesis/runtime")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| Error::InvalidJsonValue("genesis.runtime".to_owned()))?;
+
+    let session = pallet(runtime, "session")
+        .ok_or_else(|| Error::InvalidJsonValue("genesis.runtime.session".to_owned()))?;
+    session.insert(
+        "keys".to_owned(),
+        validators
+            .iter()
+            .map(|keys| json!([keys.stash, keys.stash, keys.session_keys()]))
+            .collect(),
+    );
+    // The session pallet initializes the authorities of the consensus pallets
+    for name in ["babe", "grandpa", "aura"] {
+        if let Some(pallet) = pallet(runtime, name) {
+            if pallet.contains_key("authorities") {
+                pallet.insert("authorities".to_owned(), json!([]));
+            }
+        }
+    }
+
+    if let Some(pallet) = pallet(runtime, "balances") {
+        let balances = pallet
+            .entry("balances")
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+            .ok_or_else(|| {
+                Error::InvalidJsonValue("genesis.runtime.balances.balances".to_owned())
+            })?;
+        for keys in validators {
+            for account in [&keys.stash, &keys.controller] {
+                if !balances.iter().any(|entry| entry[0] == *account) {
+                    balances.push(json!([account, ENDOWMENT]));
+                }
+            }
+        }
+    }
+
+    if let Some(staking) = pallet(runtime, "staking") {
+        staking.insert("validatorCount".to_owned(), validators.len().into());
+        staking.insert("minimumValidatorCount".to_owned(), 1.into());
+        staking.insert(
+            "stakers".to_owned(),
+            validators
+                .iter()
+                .map(|keys| json!([keys.stash, keys.controller, STASH, "Validator"]))
+                .collect(),
+        );
+        staking.insert(
+            "invulnerables".to_owned(),
+            validators.iter().map(|keys| keys.stash.as_str()).collect(),
+        );
+    }
+
+    Ok(())
+}
+
+// Genesis config of the pallet `name`, if the runtime has it
+fn pallet<'a>(
+    runtime: &'a mut Map<String, Value>,
+    name: &str,
+) -> Option<&'a mut Map<String, Value>> {
+    runtime.get_mut(name).and_then(Value::as_object_mut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(name: &str) -> ValidatorSeed {
+        ValidatorSeed {
+            name: name.to_owned(),
+            seed: format!("//{}", name),
+            bin: "polkadot".into(),
+        }
+    }
+
+    // Keys with made up addresses, named after the validator
+    fn keys(seed: &ValidatorSeed) -> ValidatorKeys {
+        ValidatorKeys {
+            seed,
+            stash: format!("{}-stash", seed.name),
+            controller: format!("{}-controller", seed.name),
+            session: ["grandpa", "babe"]
+                .into_iter()
+                .map(|name| {
+                    let (name, key_type, scheme) = SESSION_KEYS
+                        .into_iter()
+                        .find(|(known, _, _)| *known == name)
+                        .unwrap();
+                    SessionKey {
+                        name,
+                        key_type,
+                        scheme,
+                        public: format!("{}-{}", seed.name, key_type),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn spec() -> Value {
+        json!({
+            "genesis": { "runtime": {
+                "session": { "keys": [["old", "old", { "grandpa": "old", "babe": "old" }]] },
+                "babe": { "authorities": [["old", 1]] },
+                "grandpa": { "authorities": [["old", 1]] },
+                "balances": { "balances": [["bob-stash", 1]] },
+            } }
+        })
+    }
+
+    #[test]
+    fn session_key_names_come_from_the_spec() {
+        assert_eq!(session_key_names(&spec()).unwrap(), ["babe", "grandpa"]);
+
+        let spec = json!({ "genesis": { "runtime": { "session": { "keys": [] } } } });
+        assert!(session_key_names(&spec).is_err());
+    }
+
+    #[test]
+    fn validators_become_the_only_authorities() {
+        let seeds = [seed("alice"), seed("bob")];
+        let validators: Vec<_> = seeds.iter().map(keys).collect();
+        let mut spec = spec();
+        apply(&mut spec, &validators).unwrap();
+
+        let runtime = &spec["genesis"]["runtime"];
+        assert_eq!(
+            runtime["session"]["keys"],
+            json!([
+                ["alice-stash", "alice-stash", { "grandpa": "alice-gran", "babe": "alice-babe" }],
+                ["bob-stash", "bob-stash", { "grandpa": "bob-gran", "babe": "bob-babe" }]
+            ])
+        );
+        assert_eq!(runtime["babe"]["authorities"], json!([]));
+        assert_eq!(runtime["grandpa"]["authorities"], json!([]));
+        assert!(runtime.get("aura").is_none());
+        assert!(runtime.get("staking").is_none());
+    }
+
+    #[test]
+    fn accounts_are_endowed_once() {
+        let seeds = [seed("alice"), seed("bob")];
+        let validators: Vec<_> = seeds.iter().map(keys).collect();
+        let mut spec = spec();
+        apply(&mut spec, &validators).unwrap();
+
+        assert_eq!(
+            spec["genesis"]["runtime"]["balances"]["balances"],
+            json!([
+                ["bob-stash", 1],
+                ["alice-stash", ENDOWMENT],
+                ["alice-controller", ENDOWMENT],
+                ["bob-controller", ENDOWMENT]
+            ])
+        );
+    }
+
+    #[test]
+    fn stashes_are_bonded_if_the_runtime_has_staking() {
+        let seeds = [seed("alice")];
+        let validators: Vec<_> = seeds.iter().map(keys).collect();
+        let mut spec = spec();
+        spec["genesis"]["runtime"]["staking"] = json!({ "validatorCount": 3 });
+        apply(&mut spec, &validators).unwrap();
+
+        assert_eq!(
+            spec["genesis"]["runtime"]["staking"],
+            json!({
+                "validatorCount": 1,
+                "minimumValidatorCount": 1,
+                "stakers": [["alice-stash", "alice-controller", STASH, "Validator"]],
+                "invulnerables": ["alice-stash"],
+            })
+        );
+    }
+
+    #[test]
+    fn keystores_list_the_session_keys() {
+        let seed = seed("alice");
+        let keystore = keys(&seed).keystore();
+
+        assert_eq!(keystore.name, "alice");
+        assert_eq!(keystore.seed, "//alice");
+        let keys: Vec<_> = keystore
+            .keys
+            .iter()
+            .map(|key| (key.key_type.as_str(), key.scheme.as_str()))
+            .collect();
+        assert_eq!(keys, [("gran", "ed25519"), ("babe", "sr25519")]);
+    }
+}
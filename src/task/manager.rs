@@ -1,23 +1,61 @@
-use super::Task;
-use crate::Result;
+use super::{Multiplexer, OutputTarget, Phase, Task};
+use crate::{Error, Result};
 use std::{
     sync::{Arc, Condvar, Mutex},
+    thread,
     time::{Duration, Instant},
 };
 
+/// Interval at which running tasks are checked for exits
+const SUPERVISE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Interval at which a starting phase is checked for readiness
+const READY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Interval at which terminating tasks are checked for exits during shutdown
+const SHUTDOWN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Flag telling a task manager to shut down, with a condition variable notified once it's set
+pub type FinishedPair = (Mutex<bool>, Condvar);
+
 #[derive(Debug)]
 pub struct TaskManager {
-    tasks: Vec<Task>,
+    phases: Vec<Phase>,
+    startup_timeout: Duration,
+    min_peers: usize,
+    grace_period: Duration,
+    output: Option<Multiplexer>,
     start_time: Instant,
 }
 
 impl<'a> TaskManager {
-    #[inline]
-    pub fn new(tasks: Vec<Task>) -> Self {
-        Self {
-            tasks,
+    /// Creates a task manager, streaming the piped output of tasks to `output` if it's set
+    pub fn new(
+        phases: Vec<Phase>,
+        startup_timeout: Duration,
+        min_peers: usize,
+        grace_period: Duration,
+        output: Option<OutputTarget>,
+    ) -> Result<Self> {
+        let output = match output {
+            Some(target) => {
+                let names = phases
+                    .iter()
+                    .flat_map(|phase| phase.tasks().map(|task| task.name().to_owned()))
+                    .collect();
+                Some(Multiplexer::new(target, names)?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            phases,
+            startup_timeout,
+            min_peers,
+            grace_period,
+            output,
             start_time: Instant::now(),
-        }
+        })
     }
 
     // TODO: periodically output uptime
@@ -27,6 +65,7 @@ impl<'a> TaskManager {
         self.start_time.elapsed()
     }
 
+    /// Runs the tasks until SIGINT or SIGTERM is received, then shuts them down
     pub fn run(&mut self) -> Result<()> {
         // Flag for validating completion of tasks
         let finished_pair = Arc::new((Mutex::new(false), Condvar::new()));
@@ -42,26 +81,177 @@ impl<'a> TaskManager {
         };
         ctrlc::set_handler(sig_handler)?;
 
-        if let Err(err) = self.start() {
-            self.shutdown()?;
-            return Err(err);
+        self.start(&finished_pair)?;
+        self.supervise_until(&finished_pair)
+    }
+
+    /// Starts the phases in order, waiting for every task of a phase to be ready before
+    /// starting the next one, then waits for the network to be healthy
+    ///
+    /// Returns early once `finished_pair` is set, and shuts every task down on failure
+    pub fn start(&mut self, finished_pair: &FinishedPair) -> Result<()> {
+        match self.start_phases(finished_pair) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                self.shutdown()?;
+                Err(err)
+            }
         }
+    }
 
-        // Wait for the thread to finish
-        let (lock, cvar) = &*finished_pair;
-        let mut started = lock.lock().unwrap();
-        while !*started {
-            started = cvar.wait(started).unwrap();
+    /// Supervises the tasks until `finished_pair` is set, then shuts them down
+    pub fn supervise_until(&mut self, finished_pair: &FinishedPair) -> Result<()> {
+        while !wait_finished(finished_pair, SUPERVISE_INTERVAL)? {
+            if let Err(err) = self.supervise() {
+                self.shutdown()?;
+                return Err(err);
+            }
         }
 
         self.shutdown()
     }
 
-    fn start(&mut self) -> Result<()> {
-        self.tasks.iter_mut().try_for_each(|task| task.spawn())
+    fn start_phases(&mut self, finished_pair: &FinishedPair) -> Result<()> {
+        for phase in self.phases.iter_mut() {
+            phase.spawn()?;
+            attach_output(&mut self.output, phase.tasks_mut())?;
+
+            let deadline = Instant::now() + self.startup_timeout;
+            while !phase.is_ready() {
+                if wait_finished(finished_pair, READY_INTERVAL)? {
+                    return Ok(());
+                }
+
+                phase.tasks_mut().try_for_each(|task| task.supervise())?;
+                attach_output(&mut self.output, phase.tasks_mut())?;
+
+                if Instant::now() >= deadline {
+                    return Err(Error::StartupTimeout(format!(
+                        "{} not ready after {}s, waiting for {}",
+                        phase.name(),
+                        self.startup_timeout.as_secs(),
+                        phase.pending().join(", ")
+                    )));
+                }
+            }
+
+            println!("{} ready", phase.name());
+        }
+
+        self.wait_healthy(finished_pair)
+    }
+
+    // Waits for every task to report itself as healthy once all phases are started
+    fn wait_healthy(&mut self, finished_pair: &FinishedPair) -> Result<()> {
+        let deadline = Instant::now() + self.startup_timeout;
+        loop {
+            let unhealthy: Vec<&str> = self
+                .phases
+                .iter()
+                .flat_map(|phase| phase.unhealthy(self.min_peers))
+                .collect();
+
+            if unhealthy.is_empty() {
+                println!("network ready");
+                return Ok(());
+            }
+
+            if Instant::now() >= deadline {
+                return Err(Error::StartupTimeout(format!(
+                    "network not ready after {}s, waiting for {}",
+                    self.startup_timeout.as_secs(),
+                    unhealthy.join(", ")
+                )));
+            }
+
+            if wait_finished(finished_pair, READY_INTERVAL)? {
+                return Ok(());
+            }
+
+            self.supervise()?;
+        }
     }
 
+    fn tasks_mut(&mut self) -> impl Iterator<Item = &mut Task> {
+        self.phases.iter_mut().flat_map(|phase| phase.tasks_mut())
+    }
+
+    // Reaps exited tasks, applying their restart policies
+    fn supervise(&mut self) -> Result<()> {
+        self.tasks_mut().try_for_each(|task| task.supervise())?;
+
+        let tasks = self.phases.iter_mut().flat_map(|phase| phase.tasks_mut());
+        attach_output(&mut self.output, tasks)
+    }
+
+    // Stops every task, then waits for the rest of their output to be printed
     fn shutdown(&mut self) -> Result<()> {
-        self.tasks.iter_mut().try_for_each(|task| task.kill())
+        self.stop()?;
+        if let Some(output) = &mut self.output {
+            output.join();
+        }
+
+        Ok(())
+    }
+
+    // Sends SIGTERM to every task, escalating to SIGKILL for those still running once the grace
+    // period has elapsed
+    fn stop(&mut self) -> Result<()> {
+        self.tasks_mut().try_for_each(|task| task.terminate())?;
+
+        let deadline = Instant::now() + self.grace_period;
+        while Instant::now() < deadline {
+            let mut running = false;
+            for task in self.tasks_mut() {
+                task.try_wait()?;
+                running |= task.is_running();
+            }
+
+            if !running {
+                return Ok(());
+            }
+
+            thread::sleep(SHUTDOWN_INTERVAL);
+        }
+
+        let grace_period = self.grace_period;
+        for task in self.tasks_mut().filter(|task| task.is_running()) {
+            eprintln!(
+                "Node {} did not exit within {}s, killing it",
+                task.name(),
+                grace_period.as_secs()
+            );
+            task.kill()?;
+        }
+
+        Ok(())
+    }
+}
+
+// Streams the output of freshly spawned tasks
+fn attach_output<'t>(
+    output: &mut Option<Multiplexer>,
+    mut tasks: impl Iterator<Item = &'t mut Task>,
+) -> Result<()> {
+    match output {
+        Some(output) => tasks.try_for_each(|task| output.attach(task)),
+        None => Ok(()),
     }
 }
+
+/// Sets the finish flag, waking up the task manager waiting on it
+pub fn finish(finished_pair: &FinishedPair) -> Result<()> {
+    let (lock, cvar) = finished_pair;
+    *lock.lock()? = true;
+    cvar.notify_all();
+
+    Ok(())
+}
+
+// Waits up to `timeout` for the finish flag, returning whether it was set
+fn wait_finished(finished_pair: &FinishedPair, timeout: Duration) -> Result<bool> {
+    let (lock, cvar) = finished_pair;
+    let (finished, _) = cvar.wait_timeout_while(lock.lock()?, timeout, |finished| !*finished)?;
+
+    Ok(*finished)
+}
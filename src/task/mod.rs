@@ -1,27 +1,58 @@
-use crate::Result;
+use crate::{readiness::Probe, Result};
+use nix::{
+    sys::signal::{self, Signal},
+    unistd::Pid,
+};
 use std::{
     cell::RefCell,
-    process::{self, Command, ExitStatus},
+    process::{self, ChildStderr, ChildStdout, Command, ExitStatus},
+    time::Instant,
 };
 
 mod manager;
+mod output;
+mod phase;
+mod restart;
 
-pub use manager::TaskManager;
+pub use manager::{finish, FinishedPair, TaskManager};
+pub use output::{LogFilter, LogLevel, Multiplexer, OutputTarget, StderrLog};
+pub use phase::Phase;
+pub use restart::RestartPolicy;
 
 #[derive(Debug)]
 pub struct Task {
+    name: String,
     command: Command,
     handle: RefCell<Option<process::Child>>,
+    restart_policy: RestartPolicy,
+    probe: Option<Probe>,
+    restarts: u32,
+    restart_at: Option<Instant>,
 }
 
 impl Task {
-    pub const fn new(command: Command) -> Self {
+    pub const fn new(
+        name: String,
+        command: Command,
+        restart_policy: RestartPolicy,
+        probe: Option<Probe>,
+    ) -> Self {
         Self {
+            name,
             command,
             handle: RefCell::new(None),
+            restart_policy,
+            probe,
+            restarts: 0,
+            restart_at: None,
         }
     }
 
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Executes the task, waiting before returing the `ExitStatus`
     pub fn execute(&mut self) -> Result<ExitStatus> {
         Ok(self.command.status()?)
@@ -36,19 +67,105 @@ impl Task {
         Ok(())
     }
 
-    /// Kills the task if it's running
-    pub fn kill(&mut self) -> Result<()> {
+    /// Takes the piped output streams of the running process, if it has any left
+    pub fn take_output(&mut self) -> (Option<ChildStdout>, Option<ChildStderr>) {
+        match self.handle.get_mut() {
+            Some(handle) => (handle.stdout.take(), handle.stderr.take()),
+            None => (None, None),
+        }
+    }
+
+    /// Asks the task to stop by sending SIGTERM if it's running
+    pub fn terminate(&mut self) -> Result<()> {
+        self.restart_at = None;
         if let Some(handle) = self.handle.get_mut() {
+            signal::kill(Pid::from_raw(handle.id() as i32), Signal::SIGTERM)?;
+        }
+
+        Ok(())
+    }
+
+    /// Kills the task if it's running, reaping the process
+    pub fn kill(&mut self) -> Result<()> {
+        self.restart_at = None;
+        if let Some(mut handle) = self.handle.replace(None) {
             handle.kill()?;
+            handle.wait()?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.handle.borrow().is_some()
+    }
+
+    /// Whether the task is running and its ports, if probed, accept connections
+    pub fn is_ready(&self) -> bool {
+        match &self.probe {
+            Some(probe) => self.is_running() && probe.ports_open(),
+            None => self.is_running(),
+        }
+    }
+
+    /// Whether the task is running and, if probed, reports itself as healthy with at least
+    /// `min_peers` peers
+    pub fn is_healthy(&self, min_peers: usize) -> bool {
+        match &self.probe {
+            Some(probe) => self.is_running() && probe.is_ready(min_peers),
+            None => self.is_running(),
+        }
+    }
+
+    /// Reaps the process if it has exited, returning its `ExitStatus`
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+        let status = match self.handle.get_mut() {
+            Some(handle) => handle.try_wait()?,
+            None => None,
+        };
+
+        if status.is_some() {
             self.handle.replace(None);
         }
 
+        Ok(status)
+    }
+
+    /// Reports an exited process and restarts it according to the task's `RestartPolicy`
+    pub fn supervise(&mut self) -> Result<()> {
+        if let Some(restart_at) = self.restart_at {
+            if Instant::now() >= restart_at {
+                self.restart_at = None;
+                self.restarts += 1;
+                println!("Restarting {} (restart {})", self.name, self.restarts);
+                self.spawn()?;
+            }
+
+            return Ok(());
+        }
+
+        let status = match self.try_wait()? {
+            Some(status) => status,
+            None => return Ok(()),
+        };
+
+        eprintln!("Node {} died with {}", self.name, status);
+        match self.restart_policy.next_delay(status, self.restarts) {
+            Some(delay) => {
+                println!("Restarting {} in {}s", self.name, delay.as_secs());
+                self.restart_at = Some(Instant::now() + delay);
+            }
+            None => eprintln!("Node {} will not be restarted", self.name),
+        }
+
         Ok(())
     }
 }
 
 impl From<String> for Task {
     fn from(command: String) -> Self {
-        Self::new(Command::new(command))
+        let process = Command::new(&command);
+        Self::new(command, process, RestartPolicy::Never, None)
     }
 }
@@ -0,0 +1,385 @@
+use super::Task;
+use crate::{logs::RotatingLog, Error, PathBuffer, Result};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::io::AsRawFd,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+/// Colours cycled through for node name prefixes
+const COLORS: [&str; 6] = ["36", "33", "32", "35", "34", "31"];
+/// Leading fields of a log line searched for its level, substrate writes it third, after the
+/// date and time
+const LEVEL_FIELDS: usize = 4;
+
+/// Level of a substrate log line, from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    // Finds the level of a line among its leading fields, as in substrate's detailed
+    // `<date> <time> <LEVEL> <thread> <target>: ...` format, ignoring colours and returning
+    // `None` for lines without one, such as those of substrate's default format
+    fn of_line(line: &str) -> Option<Self> {
+        strip_ansi(line)
+            .split_whitespace()
+            .take(LEVEL_FIELDS)
+            .find_map(|field| match field {
+                "ERROR" => Some(Self::Error),
+                "WARN" => Some(Self::Warn),
+                "INFO" => Some(Self::Info),
+                "DEBUG" => Some(Self::Debug),
+                "TRACE" => Some(Self::Trace),
+                _ => None,
+            })
+    }
+}
+
+// `line` without the ANSI escape sequences colouring it
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Control sequences end with a character in `@..=~`
+            '\x1b' => {
+                if chars.next() == Some('[') {
+                    chars.by_ref().find(|c| ('@'..='~').contains(c));
+                }
+            }
+            c => stripped.push(c),
+        }
+    }
+
+    stripped
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err(Error::InvalidConfig(format!(
+                "unknown log level `{}`",
+                value
+            ))),
+        }
+    }
+}
+
+/// Selects the node output shown in the terminal
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Nodes to show, all nodes if empty
+    pub nodes: Vec<String>,
+    /// Least severe level to show, lines without a level are always shown
+    pub level: Option<LogLevel>,
+}
+
+impl LogFilter {
+    fn shows_node(&self, name: &str) -> bool {
+        self.nodes.is_empty() || self.nodes.iter().any(|node| node == name)
+    }
+
+    fn shows_line(&self, line: &str) -> bool {
+        match (self.level, LogLevel::of_line(line)) {
+            (Some(threshold), Some(level)) => level <= threshold,
+            _ => true,
+        }
+    }
+}
+
+/// How a node's stderr is logged alongside its stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StderrLog {
+    /// Both streams go to `<name>.log`
+    #[default]
+    Merged,
+    /// stderr goes to its own `<name>.err.log`
+    Separate,
+}
+
+impl FromStr for StderrLog {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "merged" => Ok(Self::Merged),
+            "separate" => Ok(Self::Separate),
+            _ => Err(Error::InvalidConfig(format!(
+                "unknown stderr log mode `{}`, expected `merged` or `separate`",
+                value
+            ))),
+        }
+    }
+}
+
+/// Where the output streamed from tasks goes
+#[derive(Debug, Clone)]
+pub enum OutputTarget {
+    /// Lines are printed to the terminal, prefixed with the name of their node
+    Terminal(LogFilter),
+    /// Lines are appended to per-node log files in `dir`, rotated once they exceed `max_size`
+    /// bytes
+    Files {
+        dir: PathBuffer,
+        stderr: StderrLog,
+        max_size: u64,
+    },
+}
+
+type SharedLog = Arc<Mutex<RotatingLog>>;
+
+// Destination of the lines read from a single stream
+enum Sink {
+    Terminal {
+        prefix: String,
+        filter: LogFilter,
+        shown: bool,
+    },
+    File(SharedLog),
+}
+
+impl Sink {
+    fn write_line(&self, line: &[u8]) -> Result<()> {
+        match self {
+            Self::Terminal {
+                prefix,
+                filter,
+                shown,
+            } => {
+                let line = String::from_utf8_lossy(line);
+                let line = line.trim_end_matches(&['\r', '\n'][..]);
+                if *shown && filter.shows_line(line) {
+                    writeln!(io::stdout().lock(), "{} {}", prefix, line)?;
+                }
+            }
+            Self::File(log) => log.lock()?.write_line(line)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Streams the stdout and stderr of tasks to the terminal or to log files
+#[derive(Debug)]
+pub struct Multiplexer {
+    target: OutputTarget,
+    names: Vec<String>,
+    width: usize,
+    color: bool,
+    // Logs of every node, kept open so restarted nodes append to them
+    logs: HashMap<String, (SharedLog, SharedLog)>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl Multiplexer {
+    /// Creates a multiplexer for the tasks named `names`, failing if the terminal filter selects
+    /// a node which doesn't exist
+    pub fn new(target: OutputTarget, names: Vec<String>) -> Result<Self> {
+        if let OutputTarget::Terminal(filter) = &target {
+            if let Some(unknown) = filter.nodes.iter().find(|node| !names.contains(node)) {
+                return Err(Error::InvalidConfig(format!("no node named `{}`", unknown)));
+            }
+        }
+
+        let width = names.iter().map(String::len).max().unwrap_or_default();
+        let color = nix::unistd::isatty(io::stdout().as_raw_fd()).unwrap_or(false);
+
+        Ok(Self {
+            target,
+            names,
+            width,
+            color,
+            logs: HashMap::new(),
+            readers: Vec::new(),
+        })
+    }
+
+    /// Starts streaming the output of `task` if it was just spawned with piped output
+    pub fn attach(&mut self, task: &mut Task) -> Result<()> {
+        let (stdout, stderr) = task.take_output();
+        if stdout.is_none() && stderr.is_none() {
+            return Ok(());
+        }
+
+        let (stdout_sink, stderr_sink) = self.sinks(task.name())?;
+        if let Some(stdout) = stdout {
+            self.spawn_reader(stdout, stdout_sink);
+        }
+        if let Some(stderr) = stderr {
+            self.spawn_reader(stderr, stderr_sink);
+        }
+
+        Ok(())
+    }
+
+    /// Waits for every reader to reach the end of its stream
+    pub fn join(&mut self) {
+        for reader in self.readers.drain(..) {
+            // A panicking reader has nothing left to write
+            let _ = reader.join();
+        }
+    }
+
+    // Sinks for the stdout and stderr of the task named `name`
+    fn sinks(&mut self, name: &str) -> Result<(Sink, Sink)> {
+        let (dir, stderr, max_size) = match &self.target {
+            OutputTarget::Terminal(filter) => {
+                let terminal = || Sink::Terminal {
+                    prefix: self.prefix(name),
+                    filter: filter.clone(),
+                    shown: filter.shows_node(name),
+                };
+                return Ok((terminal(), terminal()));
+            }
+            OutputTarget::Files {
+                dir,
+                stderr,
+                max_size,
+            } => (dir, stderr, *max_size),
+        };
+
+        if !self.logs.contains_key(name) {
+            let open = |file: String| -> Result<SharedLog> {
+                let log = RotatingLog::open(dir.join(file), max_size)?;
+                Ok(Arc::new(Mutex::new(log)))
+            };
+            let stdout = open(format!("{}.log", name))?;
+            let stderr = match stderr {
+                StderrLog::Merged => Arc::clone(&stdout),
+                StderrLog::Separate => open(format!("{}.err.log", name))?,
+            };
+            self.logs.insert(name.to_owned(), (stdout, stderr));
+        }
+
+        let (stdout, stderr) = &self.logs[name];
+        Ok((
+            Sink::File(Arc::clone(stdout)),
+            Sink::File(Arc::clone(stderr)),
+        ))
+    }
+
+    fn prefix(&self, name: &str) -> String {
+        let padded = format!("{:width$} |", name, width = self.width);
+        let index = self
+            .names
+            .iter()
+            .position(|n| n == name)
+            .unwrap_or_default();
+
+        match self.color {
+            true => format!("\x1b[{}m{}\x1b[0m", COLORS[index % COLORS.len()], padded),
+            false => padded,
+        }
+    }
+
+    // Streams are drained even when their lines are hidden, so nodes never block on a full pipe
+    fn spawn_reader<R>(&mut self, stream: R, sink: Sink)
+    where
+        R: Read + Send + 'static,
+    {
+        let reader = thread::spawn(move || {
+            let mut stream = BufReader::new(stream);
+            let mut line = Vec::new();
+            while let Ok(read) = stream.read_until(b'\n', &mut line) {
+                if read == 0 {
+                    break;
+                }
+
+                if let Err(err) = sink.write_line(&line) {
+                    eprintln!("Failed to write node output: {}", err);
+                }
+                line.clear();
+            }
+        });
+
+        self.readers.push(reader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_are_found_in_detailed_lines() {
+        let lines = [
+            (
+                "2024-03-12 10:14:02.118  INFO main sc_cli::runner: Parity Polkadot",
+                LogLevel::Info,
+            ),
+            (
+                "2024-03-12 10:14:08.002  WARN tokio-runtime-worker sync: [Parachain] Bad peer",
+                LogLevel::Warn,
+            ),
+            (
+                "2024-03-12 10:14:09.531 ERROR tokio-runtime-worker grandpa: Error voting",
+                LogLevel::Error,
+            ),
+            (
+                "2024-03-12 10:14:10.004 DEBUG tokio-runtime-worker libp2p_ping: Ping received",
+                LogLevel::Debug,
+            ),
+            (
+                "2024-03-12 10:14:10.250 TRACE tokio-runtime-worker sync: Block request",
+                LogLevel::Trace,
+            ),
+        ];
+
+        for (line, level) in lines {
+            assert_eq!(LogLevel::of_line(line), Some(level), "{}", line);
+        }
+    }
+
+    #[test]
+    fn colours_are_ignored() {
+        let line = "\x1b[2m2024-03-12 10:14:02.118\x1b[0m  \x1b[32mINFO\x1b[0m main \
+                    sc_cli::runner: \x1b[1;37mParity Polkadot\x1b[0m";
+        assert_eq!(LogLevel::of_line(line), Some(LogLevel::Info));
+
+        let line = "\x1b[2m2024-03-12 10:14:08\x1b[0m \x1b[33mWARN\x1b[0m sync: Bad peer";
+        assert_eq!(LogLevel::of_line(line), Some(LogLevel::Warn));
+    }
+
+    #[test]
+    fn lines_of_the_default_format_have_no_level() {
+        let lines = [
+            "2024-03-12 10:14:02 Parity Polkadot",
+            "2024-03-12 10:14:14 [Parachain] 💤 Idle (0 peers), best: #0 (0x1b2c…d3e4)",
+            "2024-03-12 10:14:14 Error importing block, INFO follows later in the message",
+            "====================",
+            "",
+        ];
+
+        for line in lines {
+            assert_eq!(LogLevel::of_line(line), None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn levels_filter_lines_without_hiding_unlevelled_ones() {
+        let filter = LogFilter {
+            nodes: Vec::new(),
+            level: Some(LogLevel::Warn),
+        };
+
+        assert!(filter.shows_line("2024-03-12 10:14:09.531 ERROR tokio-runtime-worker grandpa: x"));
+        assert!(!filter.shows_line("2024-03-12 10:14:02.118  INFO main sc_cli::runner: x"));
+        assert!(filter.shows_line("2024-03-12 10:14:02 Parity Polkadot"));
+    }
+}
@@ -0,0 +1,60 @@
+use super::Task;
+use crate::Result;
+
+/// A group of tasks which are started together and must all be ready before the next phase starts
+#[derive(Debug)]
+pub struct Phase {
+    name: String,
+    tasks: Vec<Task>,
+}
+
+impl Phase {
+    #[inline]
+    pub fn new(name: &str, tasks: Vec<Task>) -> Self {
+        Self {
+            name: name.to_owned(),
+            tasks,
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.iter()
+    }
+
+    #[inline]
+    pub fn tasks_mut(&mut self) -> impl Iterator<Item = &mut Task> {
+        self.tasks.iter_mut()
+    }
+
+    pub fn spawn(&mut self) -> Result<()> {
+        self.tasks.iter_mut().try_for_each(|task| task.spawn())
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.tasks.iter().all(|task| task.is_ready())
+    }
+
+    /// Names of the tasks which are not ready yet
+    pub fn pending(&self) -> Vec<&str> {
+        self.tasks
+            .iter()
+            .filter(|task| !task.is_ready())
+            .map(|task| task.name())
+            .collect()
+    }
+
+    /// Names of the tasks which are not healthy yet
+    pub fn unhealthy(&self, min_peers: usize) -> Vec<&str> {
+        self.tasks
+            .iter()
+            .filter(|task| !task.is_healthy(min_peers))
+            .map(|task| task.name())
+            .collect()
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::{process::ExitStatus, time::Duration};
+
+/// Upper bound for the exponential restart backoff
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+const fn default_backoff() -> u64 {
+    1
+}
+
+/// Determines whether a task is restarted after its process exits
+///
+/// `backoff` is the delay in seconds before the first restart, doubling on every
+/// subsequent restart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(
+    tag = "policy",
+    rename_all = "snake_case",
+    try_from = "RawRestartPolicy"
+)]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure {
+        max_retries: u32,
+        backoff: u64,
+    },
+    Always {
+        backoff: u64,
+    },
+}
+
+// A restart policy as written in configs, deserialized as a plain struct rather than a tagged
+// enum so unknown keys reach the caller's deserializer and are reported with their path
+#[derive(Deserialize)]
+struct RawRestartPolicy {
+    policy: PolicyKind,
+    max_retries: Option<u32>,
+    backoff: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PolicyKind {
+    Never,
+    OnFailure,
+    Always,
+}
+
+impl TryFrom<RawRestartPolicy> for RestartPolicy {
+    type Error = String;
+
+    fn try_from(raw: RawRestartPolicy) -> Result<Self, Self::Error> {
+        let backoff = raw.backoff.unwrap_or_else(default_backoff);
+        match (raw.policy, raw.max_retries) {
+            (PolicyKind::OnFailure, Some(max_retries)) => Ok(Self::OnFailure {
+                max_retries,
+                backoff,
+            }),
+            (PolicyKind::OnFailure, None) => Err("missing field `max_retries`".to_owned()),
+            (_, Some(_)) => Err("`max_retries` only applies to the on_failure policy".to_owned()),
+            (PolicyKind::Always, None) => Ok(Self::Always { backoff }),
+            (PolicyKind::Never, None) => match raw.backoff {
+                Some(_) => Err("`backoff` doesn't apply to the never policy".to_owned()),
+                None => Ok(Self::Never),
+            },
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Returns the delay before the next restart, or `None` if the task should stay down
+    pub fn next_delay(&self, status: ExitStatus, restarts: u32) -> Option<Duration> {
+        let backoff = match *self {
+            Self::Never => return None,
+            Self::OnFailure {
+                max_retries,
+                backoff,
+            } => match status.success() || restarts >= max_retries {
+                true => return None,
+                false => backoff,
+            },
+            Self::Always { backoff } => backoff,
+        };
+
+        let factor = 2u64.saturating_pow(restarts);
+        let delay = Duration::from_secs(backoff.saturating_mul(factor));
+
+        Some(delay.min(MAX_BACKOFF))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    fn exited(code: i32) -> ExitStatus {
+        ExitStatus::from_raw(code << 8)
+    }
+
+    #[test]
+    fn never_stays_down() {
+        assert_eq!(RestartPolicy::default(), RestartPolicy::Never);
+        assert_eq!(RestartPolicy::Never.next_delay(exited(1), 0), None);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RestartPolicy::Always { backoff: 2 };
+        let delays: Vec<_> = (0..4)
+            .map(|restarts| policy.next_delay(exited(0), restarts))
+            .collect();
+
+        assert_eq!(
+            delays,
+            [2, 4, 8, 16].map(|secs| Some(Duration::from_secs(secs)))
+        );
+        assert_eq!(policy.next_delay(exited(0), 20), Some(MAX_BACKOFF));
+        assert_eq!(policy.next_delay(exited(0), u32::MAX), Some(MAX_BACKOFF));
+    }
+
+    #[test]
+    fn on_failure_retries_failures_only() {
+        let policy = RestartPolicy::OnFailure {
+            max_retries: 2,
+            backoff: 1,
+        };
+
+        assert_eq!(policy.next_delay(exited(0), 0), None);
+        assert_eq!(
+            policy.next_delay(exited(1), 0),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            policy.next_delay(exited(1), 1),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(policy.next_delay(exited(1), 2), None);
+    }
+
+    #[test]
+    fn policies_reject_fields_of_other_policies() {
+        let parse = |policy: &str| {
+            serde_json::from_str::<RestartPolicy>(policy).map_err(|err| err.to_string())
+        };
+
+        assert!(parse(r#"{ "policy": "on_failure" }"#)
+            .unwrap_err()
+            .starts_with("missing field `max_retries`"));
+        assert!(parse(r#"{ "policy": "always", "max_retries": 3 }"#)
+            .unwrap_err()
+            .starts_with("`max_retries` only applies"));
+        assert!(parse(r#"{ "policy": "never", "backoff": 3 }"#)
+            .unwrap_err()
+            .starts_with("`backoff` doesn't apply"));
+    }
+
+    #[test]
+    fn policies_serialize_tagged() {
+        let policy = RestartPolicy::Always { backoff: 2 };
+        let json = serde_json::to_string(&policy).unwrap();
+
+        assert_eq!(json, r#"{"policy":"always","backoff":2}"#);
+        assert_eq!(
+            serde_json::from_str::<RestartPolicy>(&json).unwrap(),
+            policy
+        );
+    }
+
+    #[test]
+    fn policies_deserialize_with_default_backoff() {
+        let policy: RestartPolicy =
+            serde_json::from_str(r#"{ "policy": "on_failure", "max_retries": 3 }"#).unwrap();
+
+        assert_eq!(
+            policy,
+            RestartPolicy::OnFailure {
+                max_retries: 3,
+                backoff: 1
+            }
+        );
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{Error, Result};
+use crate::{error::SerdeError, Error, Result};
 use nix::unistd::Uid;
+use serde_json::Value;
 use std::{
     path::{Path, PathBuf},
     process::Output,
@@ -54,6 +55,38 @@ pub fn path_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
     }
 }
 
+/// Whether `args` contain any of `names`, either as a flag or as `<name>=<value>`
+pub fn has_arg(args: &[String], names: &[&str]) -> bool {
+    args.iter().any(|arg| {
+        names
+            .iter()
+            .any(|name| arg == name || arg.starts_with(&format!("{}=", name)))
+    })
+}
+
+/// Value of the first of `names` found in `args`, either following it or as `<name>=<value>`
+pub fn arg_value<'a>(args: &'a [String], names: &[&str]) -> Option<&'a str> {
+    args.iter().enumerate().find_map(|(index, arg)| {
+        names.iter().find_map(|name| match arg.strip_prefix(name) {
+            Some("") => args.get(index + 1).map(String::as_str),
+            Some(value) => value.strip_prefix('='),
+            None => None,
+        })
+    })
+}
+
 pub fn get_name(bin: &str, ws_port: u16) -> String {
     format!("{}-{}", bin, ws_port)
 }
+
+/// Parses the JSON document `data`, keeping its numbers as written however large they are
+pub fn parse_json(data: &[u8]) -> Result<Value> {
+    serde_json::from_slice(data)
+        .map_err(|err| Error::Serde(SerdeError::Deserialize(err.to_string())))
+}
+
+/// `data` pretty-printed with an indent of 2
+pub fn pretty_json(data: &Value) -> Result<String> {
+    serde_json::to_string_pretty(data)
+        .map_err(|err| Error::Serde(SerdeError::Serialize(err.to_string())))
+}
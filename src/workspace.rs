@@ -0,0 +1,120 @@
+use crate::{util, Config, PathBuffer, Result};
+use std::{
+    env, fs, io, process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Args with which a node manages its own chain data
+const BASE_PATH_ARGS: [&str; 3] = ["--base-path", "-d", "--tmp"];
+
+/// Number of temporary workspaces created by the process
+static TEMPORARY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Directory holding the chain data of every node, each in `<dir>/<node name>`
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub dir: PathBuffer,
+    /// Deletes the chain data of the nodes before they are started
+    pub purge: bool,
+    /// Deletes the chain data of the nodes once they are shut down
+    pub cleanup: bool,
+}
+
+impl Workspace {
+    pub fn new(dir: PathBuffer) -> Self {
+        Self {
+            dir,
+            purge: false,
+            cleanup: false,
+        }
+    }
+
+    /// Workspace of `config`, in the temporary directory unless it sets one
+    ///
+    /// Only a workspace set by the config is kept on shutdown by default.
+    pub fn of_config(config: &Config) -> Self {
+        let mut workspace = match &config.workspace {
+            Some(dir) => Self::new(dir.to_owned()),
+            None => Self::temporary(config.name.as_deref()),
+        };
+        workspace.cleanup = config.cleanup.unwrap_or(config.workspace.is_none());
+
+        workspace
+    }
+
+    /// Workspace in the system's temporary directory, named after the network along with the
+    /// process ID and a counter, so no other workspace shares it
+    pub fn temporary(network: Option<&str>) -> Self {
+        let name = format!(
+            "{}-{}-{}",
+            network.unwrap_or("network").replace('/', "-"),
+            process::id(),
+            TEMPORARY_COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let dir = env::temp_dir().join("pendulum-launch").join(name);
+
+        Self::new(PathBuffer::from(dir))
+    }
+
+    /// Chain data directory of the node named `node`
+    #[inline]
+    pub fn base_path(&self, node: &str) -> PathBuffer {
+        self.dir.join(node)
+    }
+
+    /// Creates the workspace, first deleting the chain data of `nodes` if `purge` is set
+    pub fn prepare<'a>(&self, mut nodes: impl Iterator<Item = &'a str>) -> Result<()> {
+        if self.purge {
+            nodes.try_for_each(|node| self.remove(node))?;
+        }
+        fs::create_dir_all(self.dir.as_ref())?;
+
+        Ok(())
+    }
+
+    /// Deletes the chain data of `nodes` if `cleanup` is set, along with the workspace if it's
+    /// left empty
+    pub fn clean_up<'a>(&self, mut nodes: impl Iterator<Item = &'a str>) -> Result<()> {
+        if !self.cleanup {
+            return Ok(());
+        }
+
+        nodes.try_for_each(|node| self.remove(node))?;
+        // Anything else in the workspace isn't ours to delete
+        let _ = fs::remove_dir(self.dir.as_ref());
+
+        Ok(())
+    }
+
+    fn remove(&self, node: &str) -> Result<()> {
+        match fs::remove_dir_all(self.base_path(node).as_ref()) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::temporary(None)
+    }
+}
+
+/// Whether `args` already set where a node keeps its chain data
+pub(crate) fn sets_base_path(args: &[String]) -> bool {
+    util::has_arg(args, &BASE_PATH_ARGS)
+}
+
+/// Base path a node with `args` keeps its chain data in, `None` if it runs with `--tmp`
+pub(crate) fn base_path(workspace: &Workspace, node: &str, args: &[String]) -> Option<PathBuffer> {
+    match util::arg_value(args, &["--base-path", "-d"]) {
+        Some(path) => Some(PathBuffer::from(path)),
+        None if util::has_arg(args, &["--tmp"]) => None,
+        None => Some(workspace.base_path(node)),
+    }
+}
+
+/// Whether a node with `args` keeps its chain data in a temporary directory of its own
+pub(crate) fn runs_with_tmp(args: &[String]) -> bool {
+    util::arg_value(args, &["--base-path", "-d"]).is_none() && util::has_arg(args, &["--tmp"])
+}
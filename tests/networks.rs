@@ -0,0 +1,121 @@
+#![cfg(target_os = "linux")]
+
+use lib_pendulum_launch::{ConfigBuilder, Launcher, NodeOutput, PathBuffer, RelayChainBuilder};
+use std::{
+    collections::HashSet,
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+/// Node binary recording its PID in its base path, then sleeping until it's terminated
+const STUB: &str = r#"#!/bin/sh
+while [ $# -gt 0 ]; do
+    [ "$1" = --base-path ] && base=$2
+    shift
+done
+mkdir -p "$base" && echo $$ > "$base/pid"
+exec sleep 600
+"#;
+
+const NODES: [&str; 2] = ["alice", "bob"];
+
+fn stub_bin() -> PathBuf {
+    let dir = env::temp_dir().join(format!("pendulum-launch-tests-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let bin = dir.join("polkadot");
+    fs::write(&bin, STUB).unwrap();
+    fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+    bin
+}
+
+fn launcher(bin: &Path) -> Launcher {
+    let config = ConfigBuilder::new()
+        .name("test")
+        .startup_timeout(Duration::from_secs(60))
+        .grace_period(Duration::from_secs(1))
+        .relay_chain(RelayChainBuilder::new(bin.to_owned(), "rococo-local").validators(2))
+        .build()
+        .unwrap();
+    let output = NodeOutput {
+        quiet: true,
+        ..NodeOutput::default()
+    };
+
+    Launcher::new(config, output).unwrap()
+}
+
+fn ports(launcher: &Launcher) -> HashSet<u16> {
+    launcher
+        .port_map()
+        .nodes
+        .iter()
+        .flat_map(|node| [node.port, node.ws_port, node.rpc_port])
+        .collect()
+}
+
+// Waits for the stub of every node to record its PID in the workspace `dir`
+fn wait_pids(dir: &PathBuffer) -> Vec<u32> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    NODES
+        .iter()
+        .map(|node| {
+            let pid_file = dir.join(node).as_ref().join("pid");
+            loop {
+                if let Some(pid) = fs::read_to_string(&pid_file)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse().ok())
+                {
+                    return pid;
+                }
+                assert!(Instant::now() < deadline, "{} never started", node);
+                thread::sleep(Duration::from_millis(50));
+            }
+        })
+        .collect()
+}
+
+fn is_running(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+#[test]
+fn networks_in_one_process_do_not_collide() {
+    let bin = stub_bin();
+    let mut first = launcher(&bin);
+    let mut second = launcher(&bin);
+
+    let first_ports = ports(&first);
+    let second_ports = ports(&second);
+    assert_eq!(first_ports.len(), 6);
+    assert_eq!(second_ports.len(), 6);
+    assert!(first_ports.is_disjoint(&second_ports));
+
+    let first_dir = first.context.workspace.dir.to_owned();
+    let second_dir = second.context.workspace.dir.to_owned();
+    assert_ne!(first_dir.as_ref(), second_dir.as_ref());
+
+    let first_network = first.start().unwrap();
+    let second_network = second.start().unwrap();
+    let pids: Vec<u32> = [&first_dir, &second_dir]
+        .into_iter()
+        .flat_map(wait_pids)
+        .collect();
+    assert!(pids.iter().all(|pid| is_running(*pid)));
+
+    drop(first_network);
+    drop(second_network);
+
+    assert!(pids.iter().all(|pid| !is_running(*pid)));
+    assert!(!first_dir.as_ref().exists());
+    assert!(!second_dir.as_ref().exists());
+
+    // The ports of dropped networks are handed out again
+    assert!(!ports(&launcher(&bin)).is_disjoint(&first_ports));
+
+    let _ = fs::remove_dir_all(bin.parent().unwrap());
+}